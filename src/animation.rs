@@ -0,0 +1,324 @@
+use std::io::Cursor;
+
+use gif::{ColorOutput, DecodeOptions, DisposalMethod};
+use image_webp::WebPDecoder;
+use png::{BlendOp, ColorType, DisposeOp, Transformations};
+
+/// Frames with a delay of at most 10 milliseconds are shown for 100 milliseconds, like browsers do
+const MIN_DELAY: u32 = 10;
+const DEFAULT_DELAY: u32 = 100;
+/// The decoded frames never take up more memory than this number of bytes, decoding stops at the first frame that
+/// doesn't fit
+const MAX_ANIMATION_SIZE: usize = 256 * 1024 * 1024;
+
+/// A frame of an animation, always the full size of the animation:
+///
+/// - `rgba`&rarr; The pixels of the frame, 4 bytes per pixel
+/// - `delay`&rarr; The number of milliseconds the frame is shown
+pub struct Frame {
+    pub rgba: Vec<u8>,
+    pub delay: u32,
+}
+
+/// The decoded frames of an animated GIF, WebP or PNG (APNG) image
+pub struct Animation {
+    pub width: u32,
+    pub height: u32,
+    pub frames: Vec<Frame>,
+}
+
+impl Animation {
+    fn new(width: u32, height: u32) -> Animation {
+        Animation {
+            width,
+            height,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Adds a frame, returns false without adding the frame if the animation would become too large
+    fn push(&mut self, rgba: Vec<u8>, delay: u32) -> bool {
+        let fits = frame_size(self.width as usize, self.height as usize).map_or(false, |size| {
+            (self.frames.len() + 1).saturating_mul(size) <= MAX_ANIMATION_SIZE
+        });
+        if !fits {
+            return false;
+        }
+        let delay = if delay <= MIN_DELAY {
+            DEFAULT_DELAY
+        } else {
+            delay
+        };
+        self.frames.push(Frame { rgba, delay });
+        true
+    }
+
+    /// Returns the animation if it has more than one frame
+    fn animated(self) -> Option<Animation> {
+        (self.frames.len() > 1).then_some(self)
+    }
+}
+
+/// Returns the number of bytes of a frame, or `None` if a single frame would exceed `MAX_ANIMATION_SIZE`. The size is
+/// taken from the header of the file, so it is checked before anything is allocated.
+fn frame_size(width: usize, height: usize) -> Option<usize> {
+    width
+        .checked_mul(height)?
+        .checked_mul(4)
+        .filter(|size| *size <= MAX_ANIMATION_SIZE)
+}
+
+/// A rectangle of a frame on the canvas of the animation
+#[derive(Clone, Copy)]
+struct Region {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+/// Clears a region of the canvas to transparent black
+fn clear(canvas: &mut [u8], canvas_width: usize, region: Region) {
+    for row in region.y..region.y + region.height {
+        let start = (row * canvas_width + region.x) * 4;
+        if let Some(pixels) = canvas.get_mut(start..start + region.width * 4) {
+            pixels.fill(0);
+        }
+    }
+}
+
+/// Draws the pixels of a frame onto the canvas. If `blend` is set, the frame is composited over the canvas, otherwise
+/// the pixels of the frame replace the pixels of the canvas.
+fn blit(canvas: &mut [u8], canvas_width: usize, region: Region, rgba: &[u8], blend: bool) {
+    let canvas_height = canvas.len() / 4 / canvas_width;
+    for row in 0..region.height.min(canvas_height.saturating_sub(region.y)) {
+        for column in 0..region.width.min(canvas_width.saturating_sub(region.x)) {
+            let source = (row * region.width + column) * 4;
+            let target = ((region.y + row) * canvas_width + region.x + column) * 4;
+            let (Some(src), Some(dst)) =
+                (rgba.get(source..source + 4), canvas.get(target..target + 4))
+            else {
+                continue;
+            };
+            let alpha = src[3] as u32;
+            let pixel: [u8; 4] = if !blend || alpha == 255 {
+                src.try_into().unwrap()
+            } else if alpha == 0 {
+                dst.try_into().unwrap()
+            } else {
+                // source over destination
+                let dst_alpha = dst[3] as u32 * (255 - alpha) / 255;
+                let out_alpha = alpha + dst_alpha;
+                let mix =
+                    |s: u8, d: u8| ((s as u32 * alpha + d as u32 * dst_alpha) / out_alpha) as u8;
+                [
+                    mix(src[0], dst[0]),
+                    mix(src[1], dst[1]),
+                    mix(src[2], dst[2]),
+                    out_alpha as u8,
+                ]
+            };
+            canvas[target..target + 4].copy_from_slice(&pixel);
+        }
+    }
+}
+
+/// Decodes the frames of an animated GIF; transparent pixels of a frame show the frame before
+fn decode_gif(bytes: &[u8]) -> Option<Animation> {
+    let mut options = DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    let mut decoder = options.read_info(bytes).ok()?;
+    let width = decoder.width() as usize;
+    let height = decoder.height() as usize;
+    let mut canvas = vec![0; frame_size(width, height)?];
+    let mut animation = Animation::new(width as u32, height as u32);
+
+    while let Ok(Some(frame)) = decoder.read_next_frame() {
+        let region = Region {
+            x: frame.left as usize,
+            y: frame.top as usize,
+            width: frame.width as usize,
+            height: frame.height as usize,
+        };
+        let previous = (frame.dispose == DisposalMethod::Previous).then(|| canvas.clone());
+        blit(&mut canvas, width, region, &frame.buffer, true);
+        // the delay of a GIF frame is stored in hundredths of a second
+        if !animation.push(canvas.clone(), frame.delay as u32 * 10) {
+            break;
+        }
+        match frame.dispose {
+            DisposalMethod::Background => clear(&mut canvas, width, region),
+            DisposalMethod::Previous => canvas = previous.unwrap(),
+            _ => (),
+        }
+    }
+    animation.animated()
+}
+
+/// Converts the decoded pixels of a PNG frame to RGBA
+fn png_to_rgba(color_type: ColorType, pixels: &[u8]) -> Vec<u8> {
+    match color_type {
+        ColorType::Rgba => pixels.to_vec(),
+        ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        // paletted images are expanded, see `Transformations::EXPAND`
+        ColorType::Grayscale | ColorType::Indexed => {
+            pixels.iter().flat_map(|p| [*p, *p, *p, 255]).collect()
+        }
+    }
+}
+
+/// Decodes the frames of an animated PNG. The default image of the PNG is only part of the animation if it is
+/// preceded by a frame control chunk.
+fn decode_png(bytes: &[u8]) -> Option<Animation> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(Transformations::normalize_to_color8() | Transformations::ALPHA);
+    let mut reader = decoder.read_info().ok()?;
+    reader.info().animation_control?;
+    let width = reader.info().width as usize;
+    let height = reader.info().height as usize;
+    let mut canvas = vec![0; frame_size(width, height)?];
+    let mut animation = Animation::new(width as u32, height as u32);
+    let mut buffer = vec![0; reader.output_buffer_size()];
+
+    while let Ok(output) = reader.next_frame(&mut buffer) {
+        let control = match reader.info().frame_control {
+            Some(control) => control,
+            None => continue,
+        };
+        let region = Region {
+            x: control.x_offset as usize,
+            y: control.y_offset as usize,
+            width: control.width as usize,
+            height: control.height as usize,
+        };
+        let rgba = png_to_rgba(output.color_type, &buffer[..output.buffer_size()]);
+        // the first frame is never disposed to the previous frame, there is none
+        let dispose_op = match control.dispose_op {
+            DisposeOp::Previous if animation.frames.is_empty() => DisposeOp::Background,
+            dispose_op => dispose_op,
+        };
+        let previous = (dispose_op == DisposeOp::Previous).then(|| canvas.clone());
+        blit(
+            &mut canvas,
+            width,
+            region,
+            &rgba,
+            control.blend_op == BlendOp::Over,
+        );
+        let delay_den = if control.delay_den == 0 {
+            100
+        } else {
+            control.delay_den
+        };
+        if !animation.push(
+            canvas.clone(),
+            control.delay_num as u32 * 1000 / delay_den as u32,
+        ) {
+            break;
+        }
+        match dispose_op {
+            DisposeOp::Background => clear(&mut canvas, width, region),
+            DisposeOp::Previous => canvas = previous.unwrap(),
+            DisposeOp::None => (),
+        }
+    }
+    animation.animated()
+}
+
+/// Decodes the frames of an animated WebP, the decoder composites the frames itself
+fn decode_webp(bytes: &[u8]) -> Option<Animation> {
+    let mut decoder = WebPDecoder::new(Cursor::new(bytes)).ok()?;
+    if !decoder.is_animated() {
+        return None;
+    }
+    let (width, height) = decoder.dimensions();
+    frame_size(width as usize, height as usize)?;
+    let has_alpha = decoder.has_alpha();
+    let mut animation = Animation::new(width, height);
+    let mut buffer = vec![0; decoder.output_buffer_size()?];
+
+    while let Ok(delay) = decoder.read_frame(&mut buffer) {
+        let rgba = if has_alpha {
+            buffer.clone()
+        } else {
+            png_to_rgba(ColorType::Rgb, &buffer)
+        };
+        if !animation.push(rgba, delay) {
+            break;
+        }
+    }
+    animation.animated()
+}
+
+/// Returns true if the bytes start with the signature of a format that can be animated
+pub fn is_animation_format(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"GIF8")
+        || bytes.starts_with(b"\x89PNG")
+        || (bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP"))
+}
+
+/// Decodes all frames of an animated GIF, WebP or PNG file. Returns `None` for other formats and for images with a
+/// single frame, these images are drawn from the image element.
+pub fn decode_animation(bytes: &[u8]) -> Option<Animation> {
+    if bytes.starts_with(b"GIF8") {
+        decode_gif(bytes)
+    } else if bytes.starts_with(b"\x89PNG") {
+        decode_png(bytes)
+    } else if is_animation_format(bytes) {
+        decode_webp(bytes)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_delays_are_shown_like_browsers_do() {
+        let mut animation = Animation::new(1, 1);
+        for delay in [0, 10, 11, 20, 500] {
+            animation.push(vec![0; 4], delay);
+        }
+        let delays: Vec<u32> = animation.frames.iter().map(|frame| frame.delay).collect();
+        assert_eq!(delays, vec![100, 100, 11, 20, 500]);
+    }
+
+    #[test]
+    fn large_frames_are_rejected_before_allocating() {
+        assert_eq!(frame_size(2, 3), Some(24));
+        assert_eq!(frame_size(8192, 8192), Some(MAX_ANIMATION_SIZE));
+        assert_eq!(frame_size(8192, 8193), None);
+        assert_eq!(frame_size(65535, 65535), None);
+        assert_eq!(frame_size(usize::MAX, 2), None);
+        // a GIF header that claims a size of 65535 by 65535 pixels, without frames
+        let header = [
+            b"GIF89a".as_slice(),
+            &[0xff, 0xff, 0xff, 0xff, 0, 0, 0],
+            &[0x3b],
+        ]
+        .concat();
+        assert!(decode_animation(&header).is_none());
+    }
+
+    #[test]
+    fn decoding_stops_at_the_size_limit() {
+        let mut animation = Animation::new(4096, 4096);
+        let frame_size = 4096 * 4096 * 4;
+        let mut frames = 0;
+        while animation.push(Vec::new(), 100) {
+            frames += 1;
+        }
+        // the frame that would cross the limit isn't kept
+        assert_eq!(frames, MAX_ANIMATION_SIZE / frame_size);
+        assert_eq!(animation.frames.len(), frames);
+    }
+}
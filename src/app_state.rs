@@ -1,14 +1,74 @@
 use std::rc::Rc;
 
 // use gloo_console::log;
+use serde::{Deserialize, Serialize};
 use yewdux::{Reducer, Store};
 
-use crate::{bounding_box::BoundingBox, handle_id::HandleId, position::Position};
+use crate::{
+    artboard::{Artboard, MAX_SIZE},
+    autosave_status::AutosaveStatus,
+    boards::Boards,
+    bounding_box::BoundingBox,
+    exif::Exif,
+    frame_cache,
+    guide::Guide,
+    handle_id::HandleId,
+    hit_test::{hit_test, images_in_rect, remove_alpha_mask},
+    image_cache,
+    image_property::ImageProperty,
+    image_status::ImageStatus,
+    import::{is_remote, shorten, ImportedImage},
+    object_urls,
+    orientation::Orientation,
+    page_format::PageFormat,
+    pattern_repeat::PatternRepeat,
+    pixel_ratio::PixelRatio,
+    placement_policy::PlacementPolicy,
+    position::Position,
+    renderer::Renderer,
+    resize_mode::ResizeMode,
+    saved_scene::SavedScene,
+    snap::{snap_move, snap_resize},
+    toast::Toast,
+};
+
+/// The size of the placeholder that is shown for an image that could not be loaded
+const PLACEHOLDER_WIDTH: i16 = 150;
+const PLACEHOLDER_HEIGHT: i16 = 100;
 
 /// - `anchor`&rarr; The position of the mouse down event relative to the image. In other words the offset of the mouse position.
 /// - `lock`&rarr; When an image is being dragged around, the original position is stored.
 /// The original position is used to calculate the offset to the new position
-#[derive(Default, Clone, PartialEq, Store)]
+/// - `pattern_x`, `pattern_y`&rarr; The origin of the pattern tile relative to the image.
+/// - `pattern_rotation`&rarr; The rotation of the pattern in degrees.
+/// - `pattern_repeat`&rarr; The direction(s) in which the pattern tile is repeated.
+/// - `pattern_mirror`&rarr; When set, every other tile is mirrored so that the tiles join seamlessly.
+/// - `aspect_locked`&rarr; When set, resizing keeps the ratio of the image; holding down the `ctrl` key inverts the lock
+/// while resizing. The lock is on by default for raster images. The ratio that is kept is stored in `ratio_wh`; it
+/// starts as the natural ratio and only changes when the image is resized without keeping the ratio, so the rounding
+/// of repeated locked resizes doesn't make the ratio drift.
+/// - `use_pattern`&rarr; When set, the image is drawn as a pattern that fills the surface of the image.
+/// - `resize_mode`&rarr; Whether the resize handles of a pattern resize the surface or the tile.
+/// - `decoded`&rarr; Is set as soon as decoding the downscaled working copies of the image has finished, see `image_cache`.
+/// - `status`&rarr; Whether the image is loading, has loaded or could not be loaded.
+/// - `attempts`&rarr; The number of times the image has been loaded again, after the user retried to load a broken image or
+/// after a remote image has failed to load with cors.
+/// - `cross_origin`&rarr; Remote images are loaded with cors first, so that their pixels can be read and exported.
+/// - `exif`&rarr; The metadata of a JPEG photo: orientation, capture date, camera and colour space.
+/// - `orientation`&rarr; The EXIF orientation that the app applies itself because the browser doesn't, see `import`. The
+/// natural size is the size of the upright photo.
+/// - `frame_delays`&rarr; The number of milliseconds every frame of an animated image is shown, empty for images that are
+/// not animated, see `frame_cache`.
+/// - `frame`&rarr; The index of the frame of an animated image that is shown and exported.
+/// - `playing`&rarr; Whether the animation is playing or paused.
+/// - `frame_started`&rarr; The time in milliseconds at which the current frame was shown.
+/// - `is_vector`&rarr; Is set for SVG images, these are rasterized again whenever their size changes so they stay crisp.
+/// - `tainted`&rarr; Is set if the image could only be loaded without cors; drawing the image taints the canvas, so
+/// the image can't be exported.
+///
+/// The image data is saved with the scene, see `autosave`; the state that is rebuilt after the image has loaded again
+/// is skipped.
+#[derive(Default, Clone, PartialEq, Store, Serialize, Deserialize)]
 pub struct ImageData {
     pub id: String,
     pub url: String,
@@ -20,88 +80,455 @@ pub struct ImageData {
     pub natural_height: i16,
     pub pattern_width: i16,
     pub pattern_height: i16,
+    pub pattern_x: i16,
+    pub pattern_y: i16,
+    pub pattern_rotation: i16,
+    pub pattern_repeat: PatternRepeat,
+    pub pattern_mirror: bool,
     pub ratio_wh: f32,
+    /// images that were saved before the lock existed are unlocked, like they were
+    #[serde(default)]
+    pub aspect_locked: bool,
     pub z_index: i16,
     pub use_pattern: bool,
+    pub resize_mode: ResizeMode,
+    #[serde(skip)]
+    pub decoded: bool,
+    pub status: ImageStatus,
+    pub attempts: u16,
+    pub cross_origin: bool,
+    pub tainted: bool,
+    pub exif: Option<Exif>,
+    pub orientation: Orientation,
+    #[serde(skip)]
+    pub frame_delays: Vec<u32>,
+    pub frame: usize,
+    pub playing: bool,
+    #[serde(skip)]
+    pub frame_started: f64,
+    pub is_vector: bool,
+}
+
+impl ImageData {
+    /// returns the position and the dimensions of the image
+    pub fn bounding_box(&self) -> BoundingBox {
+        BoundingBox {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
 }
+
 /// - `active_handle`&rarr; Is set as soon as the user clicks on a resize handle
 /// - `active_image_index`&rarr; Is set as soon as the user clicks on an image or when the user clicks on a resize handle of that image
+/// - `selected`&rarr; The ids of the selected images, an image is selected by clicking on it or by dragging a marquee over it
+/// - `hover`&rarr; The id of the image under the mouse pointer
+/// - `marquee`&rarr; The selection rectangle, set as long as the user drags on an empty part of the stage. The position where
+/// the user started dragging is stored in `anchor`
+/// - `pattern_lock`&rarr; When a tile is being resized, the original origin and size of the tile is stored.
+/// - `next_image_id`&rarr; The id of the next image that is added; ids are never reused, also not after an image has been removed
+/// - `toasts`&rarr; The messages that are shown to the user, for instance when a file can't be imported
+/// - `renderer`&rarr; Whether the images are rendered as separate html elements or on a single canvas
+/// - `placement_policy`&rarr; Determines the size of an image as soon as it has loaded
+/// - `viewport`&rarr; The width and height of the browser window, loaded images are moved so that they are fully visible
+/// - `pixel_ratio`&rarr; The number of device pixels per css pixel, the canvases are drawn at this resolution
+/// - `autosave`&rarr; Whether the scene is saved automatically, see `AutosaveStatus`
+/// - `revision`&rarr; Is increased by every message that may change the part of the state that is saved, see
+/// `SavedScene`; the scene is saved when the revision changes
+/// - `artboard`&rarr; The fixed size area that is exported, see `Artboard`
+/// - `guides`&rarr; The guide lines that images snap to, see `Guide`
+/// - `page_format`&rarr; The size of the page of the pdf export, see `PageFormat`
+/// - `boards`&rarr; The boards the user works on. The images and settings above belong to the active board, see `Board`
+/// - `drag_pattern`&rarr; Is set when the user clicks on a pattern while holding down the `alt` key; moving the mouse
+/// now moves the origin of the pattern tile instead of the image. Pressing a resize handle with the `alt` key held down
+/// resizes the image about its centre instead: a resize handle always takes precedence over the image below it
+/// - `drag_guide`&rarr; The index of the guide the user drags, a new guide is dragged out of a ruler
 #[derive(Default, Clone, PartialEq, Store)]
 pub struct AppState {
     pub images: Vec<ImageData>,
     pub mouse: Position,
     pub anchor: Position,
     pub lock: BoundingBox,
+    pub pattern_lock: BoundingBox,
     pub active_handle: Option<HandleId>,
     pub active_image_index: Option<usize>,
+    pub selected: Vec<String>,
+    pub hover: Option<String>,
+    pub marquee: Option<BoundingBox>,
+    pub drag_pattern: bool,
+    pub drag_guide: Option<usize>,
     pub ctrl_key_down: bool,
-    pub shift_key_down: bool,
+    pub alt_key_down: bool,
     pub next_z_index: i16,
+    pub next_image_id: usize,
+    pub renderer: Renderer,
+    pub toasts: Vec<Toast>,
+    pub next_toast_id: usize,
+    pub placement_policy: PlacementPolicy,
+    pub viewport: (i16, i16),
+    pub pixel_ratio: PixelRatio,
+    pub autosave: AutosaveStatus,
+    pub revision: u64,
+    pub artboard: Artboard,
+    pub guides: Vec<Guide>,
+    pub page_format: PageFormat,
+    pub boards: Boards,
 }
 
 #[derive(Clone, Default)]
 pub enum Msg {
-    AddImages(Vec<String>),
-    ImageLoaded(String, i16, i16, i16, i16),
+    AddImages(Vec<ImportedImage>, Option<Position>),
+    ImageLoaded(String, i16, i16),
+    ImageDecoded(String),
+    ImageFailed(String),
+    AnimationLoaded(String, Vec<u32>),
+    AdvanceFrames(f64),
+    TogglePlayback(Option<String>),
+    StepFrame(i16),
+    RetryImage(String),
     SetActiveHandle(HandleId, String, i16, i16),
-    SetActiveImage(String, i16, i16),
     RemoveImage(Option<String>),
+    ResetScene,
+    MouseDown(i16, i16),
     MouseMove(i16, i16),
     MouseUp,
+    DoubleClick(i16, i16),
     CtrlKeyDown(bool),
-    ShiftKeyDown(bool),
+    AltKeyDown(bool),
     ImageToFront,
     ImageToBack,
+    RotatePattern(i16),
+    NextPatternRepeat,
+    TogglePatternMirror,
+    TogglePattern(String),
+    ToggleResizeMode(String),
+    SetImageProperty(String, ImageProperty, i16, bool),
+    ToggleAspectLock(String),
+    ResetRatio(String),
+    ToggleRenderer,
+    ShowToast(String),
+    DismissToast(usize),
+    SetViewport(i16, i16),
+    SetPixelRatio(PixelRatio),
+    SetAutosave(AutosaveStatus),
+    RestoreScene(SavedScene),
+    AddBoard,
+    SwitchBoard(usize),
+    RenameBoard(usize, String),
+    DeleteBoard(usize),
+    NextPlacementPolicy,
+    SetPlacementValue(i16),
+    NextPageFormat,
+    NextArtboardPreset,
+    AddGuide(Guide),
+    DragGuide(usize),
+    SetArtboardSize(i16, i16),
+    SetArtboardBackground(Option<String>),
     #[default]
     None,
 }
 
+/// Moves the image so that it is fully visible in the viewport; images that are larger than the viewport are aligned
+/// with the top left corner of the viewport
+fn keep_in_viewport(img_data: &mut ImageData, (viewport_width, viewport_height): (i16, i16)) {
+    if viewport_width == 0 || viewport_height == 0 {
+        return;
+    }
+    img_data.x = img_data.x.min(viewport_width - img_data.width).max(0);
+    img_data.y = img_data.y.min(viewport_height - img_data.height).max(0);
+}
+
+/// Adds a message to the messages that are shown to the user
+fn show_toast(state: &mut AppState, message: String) {
+    state.toasts.push(Toast {
+        id: state.next_toast_id,
+        message,
+    });
+    state.next_toast_id += 1;
+}
+
+/// Returns the indices of the images a command applies to: the active image or else the selected images
+fn target_indices(state: &AppState) -> Vec<usize> {
+    match state.active_image_index {
+        Some(i) => vec![i],
+        None => state
+            .images
+            .iter()
+            .enumerate()
+            .filter(|(_, img)| state.selected.contains(&img.id))
+            .map(|(i, _)| i)
+            .collect(),
+    }
+}
+
+/// Removes the image at index `i` and everything that refers to the image
+fn remove_image(state: &mut AppState, i: usize) {
+    let img_data = state.images.remove(i);
+    state.selected.retain(|id| *id != img_data.id);
+    if state.hover.as_ref() == Some(&img_data.id) {
+        state.hover = None;
+    }
+    state.active_image_index = None;
+    release_resources(&img_data);
+}
+
+/// Releases everything that has been allocated for an image outside of the store: the object url, the decoded levels
+/// and the alpha mask
+fn release_resources(img_data: &ImageData) {
+    object_urls::release(&img_data.url, &img_data.id);
+    release_decoded(img_data);
+}
+
+/// Releases the copies of an image that are created after the image has loaded: the decoded levels, the frames and the
+/// alpha mask. These are created again as soon as the image is shown again.
+fn release_decoded(img_data: &ImageData) {
+    image_cache::remove(&img_data.id);
+    frame_cache::remove(&img_data.id);
+    remove_alpha_mask(&img_data.id);
+}
+
+/// Shows the images and settings of the active board, everything that refers to the images of the board that was shown
+/// before is reset
+fn load_active_board(state: &mut AppState) {
+    let board = &mut state.boards.list[state.boards.active];
+    state.images = std::mem::take(&mut board.images);
+    state.next_z_index = board.next_z_index;
+    state.renderer = board.renderer;
+    state.placement_policy = board.placement_policy;
+    state.artboard = board.artboard.clone();
+    state.guides = board.guides.clone();
+    state.drag_guide = None;
+    state.selected.clear();
+    state.hover = None;
+    state.marquee = None;
+    state.active_handle = None;
+    state.active_image_index = None;
+}
+
+/// Copies the images and settings of the active board to the list of boards and shows the board at index `i`. The
+/// images of the board that is hidden keep their object urls, only the copies that are created after loading are
+/// released.
+fn switch_board(state: &mut AppState, i: usize) {
+    if i == state.boards.active {
+        return;
+    }
+    let mut images = std::mem::take(&mut state.images);
+    for img_data in images.iter_mut() {
+        release_decoded(img_data);
+        img_data.decoded = false;
+        img_data.frame_delays.clear();
+        img_data.frame_started = 0.0;
+    }
+    let board = &mut state.boards.list[state.boards.active];
+    board.images = images;
+    board.next_z_index = state.next_z_index;
+    board.renderer = state.renderer;
+    board.placement_policy = state.placement_policy;
+    board.artboard = state.artboard.clone();
+    board.guides = state.guides.clone();
+    state.boards.active = i;
+    load_active_board(state);
+}
+
+impl Msg {
+    /// Returns true if the message may change the part of the state that is saved. Messages that only change the
+    /// session, like the position of the mouse or the playback of animations, don't.
+    fn changes_scene(&self, state: &AppState) -> bool {
+        match self {
+            Msg::MouseMove(_, _) => {
+                state.active_image_index.is_some() || state.drag_guide.is_some()
+            }
+            Msg::MouseUp => state.drag_guide.is_some(),
+            Msg::ImageDecoded(_)
+            | Msg::AnimationLoaded(_, _)
+            | Msg::AdvanceFrames(_)
+            | Msg::TogglePlayback(_)
+            | Msg::StepFrame(_)
+            | Msg::MouseDown(_, _)
+            | Msg::CtrlKeyDown(_)
+            | Msg::AltKeyDown(_)
+            | Msg::ShowToast(_)
+            | Msg::DismissToast(_)
+            | Msg::SetViewport(_, _)
+            | Msg::SetPixelRatio(_)
+            | Msg::SetAutosave(_)
+            | Msg::NextPageFormat
+            | Msg::DragGuide(_)
+            | Msg::None => false,
+            _ => true,
+        }
+    }
+}
+
+/// Returns true if the animation of the image is playing and has to show the next frame, or has to start playing
+fn frame_is_due(img_data: &ImageData, now: f64) -> bool {
+    if !img_data.playing || img_data.frame_delays.is_empty() {
+        return false;
+    }
+    // an animation that starts playing takes the current time as the start of its frame
+    img_data.frame_started == 0.0
+        || img_data
+            .frame_delays
+            .get(img_data.frame)
+            .map_or(true, |delay| now - img_data.frame_started >= *delay as f64)
+}
+
 impl Reducer<AppState> for Msg {
     fn apply(self, mut app_state: Rc<AppState>) -> Rc<AppState> {
+        // the frames are checked about 60 times a second, most checks don't advance a frame and shouldn't copy the state
+        if let Msg::AdvanceFrames(now) = self {
+            if !app_state.images.iter().any(|img| frame_is_due(img, now)) {
+                return app_state;
+            }
+        }
+        let changes_scene = self.changes_scene(&app_state);
         let state = Rc::make_mut(&mut app_state);
         match self {
             Msg::MouseUp => {
                 state.active_handle = None;
                 state.active_image_index = None;
+                state.drag_pattern = false;
+                state.marquee = None;
+                // guides that are dropped back onto their ruler are removed
+                if let Some(i) = state.drag_guide.take() {
+                    if state.guides[i].is_on_ruler() {
+                        state.guides.remove(i);
+                    }
+                }
             }
             Msg::MouseMove(x, y) => {
                 state.mouse.x = x;
                 state.mouse.y = y;
+                if let Some(i) = state.drag_guide {
+                    state.guides[i] = state.guides[i].moved_to(x, y);
+                } else if state.marquee.is_some() {
+                    let marquee = BoundingBox {
+                        x: x.min(state.anchor.x),
+                        y: y.min(state.anchor.y),
+                        width: (x - state.anchor.x).abs(),
+                        height: (y - state.anchor.y).abs(),
+                    };
+                    state.marquee = Some(marquee);
+                    state.selected = images_in_rect(&state.images, marquee)
+                        .into_iter()
+                        .map(|i| state.images[i].id.clone())
+                        .collect();
+                } else if state.active_image_index.is_none() {
+                    state.hover = hit_test(&state.images, state.mouse, true)
+                        .map(|i| state.images[i].id.clone());
+                }
                 if let Some(index) = state.active_image_index {
                     let handle = state.active_handle.clone();
                     if let Some(handle) = handle {
+                        // with the alt key held down the image is resized about its centre
+                        let calculate = if state.alt_key_down {
+                            HandleId::calculate_bounding_box_from_centre
+                        } else {
+                            HandleId::calculate_bounding_box
+                        };
                         let img_data = &mut state.images[index];
-                        let bb = handle.calculate_bounding_box(
-                            state.lock,
-                            state.anchor,
-                            state.mouse,
-                            img_data.ratio_wh,
-                            state.ctrl_key_down,
-                        );
-                        img_data.x = bb.x;
-                        img_data.y = bb.y;
-                        img_data.width = bb.width;
-                        img_data.height = bb.height;
-                        if !state.shift_key_down {
-                            img_data.pattern_width = bb.width;
-                            img_data.pattern_height = bb.height;
+                        if img_data.use_pattern && img_data.resize_mode == ResizeMode::Tile {
+                            // the tile is scaled by the same factor as the surface would have been scaled
+                            let lock = state.lock;
+                            let bb = calculate(
+                                &handle,
+                                lock,
+                                state.anchor,
+                                state.mouse,
+                                lock.width as f32 / lock.height as f32,
+                                img_data.aspect_locked != state.ctrl_key_down,
+                            );
+                            let sw = bb.width as f32 / lock.width as f32;
+                            let sh = bb.height as f32 / lock.height as f32;
+                            img_data.pattern_width =
+                                ((state.pattern_lock.width as f32 * sw) as i16).max(1);
+                            img_data.pattern_height =
+                                ((state.pattern_lock.height as f32 * sh) as i16).max(1);
+                        } else {
+                            let keep_ratio = img_data.aspect_locked != state.ctrl_key_down;
+                            let bb = calculate(
+                                &handle,
+                                state.lock,
+                                state.anchor,
+                                state.mouse,
+                                img_data.ratio_wh,
+                                keep_ratio,
+                            );
+                            // snapping a single edge would change the ratio or move the centre
+                            let bb = if keep_ratio || state.alt_key_down {
+                                bb
+                            } else {
+                                snap_resize(bb, handle.edges(), &state.guides)
+                            };
+                            img_data.x = bb.x;
+                            img_data.y = bb.y;
+                            img_data.width = bb.width;
+                            img_data.height = bb.height;
+                            // a free resize sets the ratio that the next locked resize keeps
+                            if !keep_ratio {
+                                img_data.ratio_wh = bb.width as f32 / bb.height as f32;
+                            }
+                            if !img_data.use_pattern {
+                                img_data.pattern_width = bb.width;
+                                img_data.pattern_height = bb.height;
+                            }
                         }
+                    } else if state.drag_pattern {
+                        let img_data = &mut state.images[index];
+                        img_data.pattern_x =
+                            state.pattern_lock.x + (x - img_data.x - state.anchor.x);
+                        img_data.pattern_y =
+                            state.pattern_lock.y + (y - img_data.y - state.anchor.y);
                     } else {
                         let img_data = &mut state.images[index];
-                        img_data.x = x - state.anchor.x;
-                        img_data.y = y - state.anchor.y;
+                        let bb = snap_move(
+                            BoundingBox {
+                                x: x - state.anchor.x,
+                                y: y - state.anchor.y,
+                                ..img_data.bounding_box()
+                            },
+                            &state.guides,
+                        );
+                        img_data.x = bb.x;
+                        img_data.y = bb.y;
                     }
                     // log!("Msg::MouseMove position", img_data.x, img_data.y);
                     // log!("Msg::MouseMove bounding box", bb.to_string());
                 }
             }
-            Msg::SetActiveImage(image_id, anchor_x, anchor_y) => {
-                let index = state.images.iter().position(|d| d.id == image_id);
-                if let Some(i) = index {
+            // the press on a resize handle has started a resize, the image below the handle isn't dragged
+            Msg::MouseDown(_, _) if state.active_handle.is_some() => (),
+            Msg::MouseDown(x, y) => {
+                let mouse = Position { x, y };
+                if let Some(i) = hit_test(&state.images, mouse, true) {
+                    let img_data = &state.images[i];
                     state.active_image_index = Some(i);
-                    state.anchor.x = anchor_x;
-                    state.anchor.y = anchor_y;
-                    // log!("Msg::SetActiveImage", state.active_image_index);
+                    state.anchor.x = x - img_data.x;
+                    state.anchor.y = y - img_data.y;
+                    state.selected = vec![img_data.id.clone()];
+                    state.drag_pattern = state.alt_key_down && img_data.use_pattern;
+                    if state.drag_pattern {
+                        state.pattern_lock.x = img_data.pattern_x;
+                        state.pattern_lock.y = img_data.pattern_y;
+                    }
+                    // log!("Msg::MouseDown", state.active_image_index);
+                } else {
+                    state.anchor = mouse;
+                    state.selected.clear();
+                    state.marquee = Some(BoundingBox {
+                        x,
+                        y,
+                        width: 0,
+                        height: 0,
+                    });
+                }
+            }
+            Msg::DoubleClick(x, y) => {
+                if let Some(i) = hit_test(&state.images, Position { x, y }, true) {
+                    remove_image(state, i);
+                    // log!("Msg::DoubleClick", i);
                 }
             }
             Msg::SetActiveHandle(handle_id, image_id, anchor_x, anchor_y) => {
@@ -109,27 +536,47 @@ impl Reducer<AppState> for Msg {
                 if let Some(i) = index {
                     state.active_handle = Some(handle_id.clone());
                     state.active_image_index = Some(i);
+                    // with the alt key held down a handle resizes about the centre, it never drags the pattern
+                    state.drag_pattern = false;
+                    state.selected = vec![image_id];
                     state.anchor.x = anchor_x;
                     state.anchor.y = anchor_y;
-                    let img_data = &mut state.images[i];
+                    let img_data = &state.images[i];
                     state.lock.x = img_data.x;
                     state.lock.y = img_data.y;
                     state.lock.width = img_data.width;
                     state.lock.height = img_data.height;
-                    img_data.use_pattern = state.shift_key_down;
+                    state.pattern_lock.x = img_data.pattern_x;
+                    state.pattern_lock.y = img_data.pattern_y;
+                    state.pattern_lock.width = img_data.pattern_width;
+                    state.pattern_lock.height = img_data.pattern_height;
                 }
             }
-            Msg::AddImages(urls) => {
-                let mut x = 50;
-                let mut y = 50;
-                let mut index = state.images.len();
+            Msg::AddImages(images, position) => {
+                // dropped images are placed where they were dropped, other images start at a fixed position
+                let Position { mut x, mut y } = position.unwrap_or(Position { x: 50, y: 50 });
 
-                for url in urls.clone() {
+                for ImportedImage {
+                    url,
+                    exif,
+                    orientation,
+                    is_vector,
+                } in images
+                {
                     let z_index = state.next_z_index + 1;
                     state.next_z_index = z_index;
+                    let id = state.next_image_id.to_string();
+                    state.next_image_id += 1;
+                    object_urls::retain(&url, &id);
                     let new_image = ImageData {
-                        id: index.to_string(),
-                        url: url.clone(),
+                        id,
+                        cross_origin: is_remote(&url),
+                        url,
+                        exif,
+                        orientation,
+                        is_vector,
+                        // photos keep their ratio, vector images like icons and shapes are often stretched
+                        aspect_locked: !is_vector,
                         x,
                         y,
                         width: 0,
@@ -141,42 +588,253 @@ impl Reducer<AppState> for Msg {
                         ratio_wh: 0.0,
                         z_index,
                         use_pattern: false,
+                        ..Default::default()
                     };
                     state.images.push(new_image);
                     x += 30;
                     y += 30;
-                    index += 1;
                 }
                 // let length = state.images.len();
                 // log!("Msg::AddImage", url.clone(), length);
             }
-            Msg::ImageLoaded(id, width, height, natural_width, natural_height) => {
+            Msg::ImageLoaded(id, natural_width, natural_height) => {
                 let index = state.images.iter().position(|d| d.id == id);
                 if let Some(i) = index {
                     let img_data = &mut state.images[i];
+                    let (natural_width, natural_height) = if img_data.orientation.swaps_axes() {
+                        (natural_height, natural_width)
+                    } else {
+                        (natural_width, natural_height)
+                    };
+                    let (width, height) = state.placement_policy.initial_size(
+                        natural_width,
+                        natural_height,
+                        state.viewport,
+                    );
                     let r: f32 = width as f32 / height as f32;
                     img_data.ratio_wh = r;
-                    img_data.width = 300;
-                    img_data.height = (1.0 / r * 300.0) as i16;
+                    img_data.width = width;
+                    img_data.height = height;
                     img_data.natural_width = natural_width;
                     img_data.natural_height = natural_height;
                     img_data.pattern_width = img_data.width;
                     img_data.pattern_height = img_data.height;
+                    img_data.status = ImageStatus::Loaded;
+                    keep_in_viewport(img_data, state.viewport);
                     // log!("Msg::ImageLoaded", width, height, r);
                 }
             }
+            Msg::ImageDecoded(id) => {
+                let index = state.images.iter().position(|d| d.id == id);
+                if let Some(i) = index {
+                    state.images[i].decoded = true;
+                    // log!("Msg::ImageDecoded", id);
+                }
+            }
+            Msg::ImageFailed(id) => {
+                let index = state.images.iter().position(|d| d.id == id);
+                if let Some(i) = index {
+                    let img_data = &mut state.images[i];
+                    if img_data.cross_origin {
+                        // the server doesn't allow cors, the image is loaded again without cors
+                        img_data.cross_origin = false;
+                        img_data.tainted = true;
+                        img_data.attempts += 1;
+                        let message = shorten(&img_data.url)
+                            + ": loaded without cors, this image can't be exported";
+                        show_toast(state, message);
+                    } else {
+                        img_data.status = ImageStatus::Broken;
+                        img_data.width = PLACEHOLDER_WIDTH;
+                        img_data.height = PLACEHOLDER_HEIGHT;
+                        img_data.ratio_wh = PLACEHOLDER_WIDTH as f32 / PLACEHOLDER_HEIGHT as f32;
+                        img_data.use_pattern = false;
+                        keep_in_viewport(img_data, state.viewport);
+                    }
+                    // log!("Msg::ImageFailed", id);
+                }
+            }
+            Msg::AnimationLoaded(id, frame_delays) => {
+                let index = state.images.iter().position(|d| d.id == id);
+                if let Some(i) = index {
+                    let img_data = &mut state.images[i];
+                    img_data.frame_delays = frame_delays;
+                    img_data.frame = 0;
+                    img_data.playing = true;
+                    img_data.frame_started = 0.0;
+                    // log!("Msg::AnimationLoaded", id);
+                }
+            }
+            Msg::AdvanceFrames(now) => {
+                for img_data in state.images.iter_mut() {
+                    if !img_data.playing || img_data.frame_delays.is_empty() {
+                        continue;
+                    }
+                    if img_data.frame_started == 0.0 {
+                        img_data.frame_started = now;
+                    }
+                    // frames are skipped if the browser couldn't keep up, for instance when the tab was hidden
+                    let mut elapsed = now - img_data.frame_started;
+                    let total = img_data.frame_delays.iter().sum::<u32>() as f64;
+                    if total > 0.0 {
+                        elapsed %= total;
+                        img_data.frame_started = now - elapsed;
+                    }
+                    while elapsed >= img_data.frame_delays[img_data.frame] as f64 {
+                        let delay = img_data.frame_delays[img_data.frame] as f64;
+                        elapsed -= delay;
+                        img_data.frame_started += delay;
+                        img_data.frame = (img_data.frame + 1) % img_data.frame_delays.len();
+                    }
+                }
+            }
+            Msg::TogglePlayback(id) => {
+                let indices = match id {
+                    Some(id) => state
+                        .images
+                        .iter()
+                        .position(|d| d.id == id)
+                        .into_iter()
+                        .collect(),
+                    None => target_indices(state),
+                };
+                for i in indices {
+                    let img_data = &mut state.images[i];
+                    if !img_data.frame_delays.is_empty() {
+                        img_data.playing = !img_data.playing;
+                        img_data.frame_started = 0.0;
+                        // log!("Msg::TogglePlayback", img_data.playing);
+                    }
+                }
+            }
+            Msg::StepFrame(step) => {
+                for i in target_indices(state) {
+                    let img_data = &mut state.images[i];
+                    let count = img_data.frame_delays.len() as i16;
+                    if count > 0 {
+                        // stepping through the frames pauses the animation, so a frame can be picked for export
+                        img_data.playing = false;
+                        img_data.frame = (img_data.frame as i16 + step).rem_euclid(count) as usize;
+                        // log!("Msg::StepFrame", img_data.frame);
+                    }
+                }
+            }
+            Msg::RetryImage(id) => {
+                let index = state.images.iter().position(|d| d.id == id);
+                if let Some(i) = index {
+                    let img_data = &mut state.images[i];
+                    img_data.status = ImageStatus::Loading;
+                    img_data.width = 0;
+                    img_data.height = 0;
+                    img_data.attempts += 1;
+                    img_data.cross_origin = is_remote(&img_data.url);
+                    img_data.tainted = false;
+                    // log!("Msg::RetryImage", id, img_data.attempts);
+                }
+            }
             Msg::RemoveImage(id) => {
                 if let Some(id) = id {
                     let index = state.images.iter().position(|d| d.id == id);
                     if let Some(i) = index {
-                        state.images.remove(i);
-                        state.active_image_index = None;
+                        remove_image(state, i);
                         // log!("Msg::RemoveImage", i);
                     }
                 } else if let Some(i) = state.active_image_index {
-                    state.images.remove(i);
-                    state.active_image_index = None;
+                    remove_image(state, i);
                     // log!("Msg::RemoveImage", i);
+                } else {
+                    for id in state.selected.clone() {
+                        let index = state.images.iter().position(|d| d.id == id);
+                        if let Some(i) = index {
+                            remove_image(state, i);
+                        }
+                    }
+                }
+            }
+            Msg::ResetScene => {
+                for img_data in state.images.iter() {
+                    release_resources(img_data);
+                }
+                state.images.clear();
+                state.selected.clear();
+                state.hover = None;
+                state.active_handle = None;
+                state.active_image_index = None;
+                state.marquee = None;
+                state.drag_pattern = false;
+                state.next_z_index = 0;
+                // log!("Msg::ResetScene");
+            }
+            Msg::SetImageProperty(id, property, value, keep_ratio) => {
+                let index = state.images.iter().position(|d| d.id == id);
+                if let Some(i) = index {
+                    let img_data = &mut state.images[i];
+                    // the other dimension follows the stored ratio, like while resizing, so setting the size
+                    // repeatedly doesn't let the ratio drift; the current size is used if there is no valid ratio
+                    let ratio_wh = if img_data.ratio_wh.is_finite() && img_data.ratio_wh > 0. {
+                        img_data.ratio_wh
+                    } else {
+                        img_data.width as f32 / img_data.height.max(1) as f32
+                    };
+                    let clamped = |size: f32| (size.round() as i16).clamp(1, MAX_SIZE);
+                    match property {
+                        ImageProperty::X => img_data.x = value,
+                        ImageProperty::Y => img_data.y = value,
+                        ImageProperty::Width => {
+                            if keep_ratio && ratio_wh > 0. {
+                                img_data.height = clamped(value as f32 / ratio_wh);
+                            }
+                            img_data.width = value;
+                        }
+                        ImageProperty::Height => {
+                            if keep_ratio {
+                                img_data.width = clamped(value as f32 * ratio_wh);
+                            }
+                            img_data.height = value;
+                        }
+                        ImageProperty::TileWidth => img_data.pattern_width = value,
+                        ImageProperty::TileHeight => img_data.pattern_height = value,
+                        ImageProperty::ZIndex => {
+                            img_data.z_index = value;
+                            state.next_z_index = state.next_z_index.max(value);
+                        }
+                    }
+                    // a size that is set without keeping the ratio sets the ratio, like a free resize
+                    let is_size = matches!(property, ImageProperty::Width | ImageProperty::Height);
+                    if is_size && !keep_ratio && img_data.height > 0 {
+                        img_data.ratio_wh = img_data.width as f32 / img_data.height as f32;
+                    }
+                    // the tile of a plain image has the size of the image, like while resizing
+                    if !img_data.use_pattern {
+                        img_data.pattern_width = img_data.width;
+                        img_data.pattern_height = img_data.height;
+                    }
+                    // log!("Msg::SetImageProperty", property.to_string(), value);
+                }
+            }
+            Msg::ToggleAspectLock(id) => {
+                let index = state.images.iter().position(|d| d.id == id);
+                if let Some(i) = index {
+                    let img_data = &mut state.images[i];
+                    img_data.aspect_locked = !img_data.aspect_locked;
+                    // log!("Msg::ToggleAspectLock", img_data.aspect_locked);
+                }
+            }
+            Msg::ResetRatio(id) => {
+                let index = state.images.iter().position(|d| d.id == id);
+                if let Some(i) = index {
+                    let img_data = &mut state.images[i];
+                    // the width stays the same, the height follows from the natural ratio
+                    if img_data.natural_width > 0 && img_data.natural_height > 0 {
+                        let ratio = img_data.natural_width as f32 / img_data.natural_height as f32;
+                        img_data.ratio_wh = ratio;
+                        img_data.height =
+                            ((img_data.width as f32 / ratio).round() as i16).clamp(1, MAX_SIZE);
+                        if !img_data.use_pattern {
+                            img_data.pattern_height = img_data.height;
+                        }
+                    }
+                    // log!("Msg::ResetRatio", img_data.width, img_data.height);
                 }
             }
             Msg::ImageToFront => {
@@ -198,17 +856,629 @@ impl Reducer<AppState> for Msg {
                     // log!("Msg::ImageToBack", z_index);
                 }
             }
+            Msg::RotatePattern(degrees) => {
+                if let Some(i) = state.active_image_index {
+                    let img_data = &mut state.images[i];
+                    img_data.pattern_rotation = (img_data.pattern_rotation + degrees) % 360;
+                    // log!("Msg::RotatePattern", img_data.pattern_rotation);
+                }
+            }
+            Msg::NextPatternRepeat => {
+                if let Some(i) = state.active_image_index {
+                    let img_data = &mut state.images[i];
+                    img_data.pattern_repeat = img_data.pattern_repeat.next();
+                    // log!("Msg::NextPatternRepeat", img_data.pattern_repeat.to_string());
+                }
+            }
+            Msg::TogglePatternMirror => {
+                if let Some(i) = state.active_image_index {
+                    let img_data = &mut state.images[i];
+                    img_data.pattern_mirror = !img_data.pattern_mirror;
+                    // log!("Msg::TogglePatternMirror", img_data.pattern_mirror);
+                }
+            }
+            Msg::TogglePattern(id) => {
+                let index = state.images.iter().position(|d| d.id == id);
+                if let Some(i) = index {
+                    let img_data = &mut state.images[i];
+                    if img_data.use_pattern {
+                        // converting back to a plain image: the image shows a single tile again
+                        img_data.width = img_data.pattern_width;
+                        img_data.height = img_data.pattern_height;
+                        img_data.pattern_x = 0;
+                        img_data.pattern_y = 0;
+                        img_data.pattern_rotation = 0;
+                        img_data.use_pattern = false;
+                        img_data.resize_mode = ResizeMode::Surface;
+                    } else {
+                        img_data.use_pattern = true;
+                    }
+                    // log!("Msg::TogglePattern", img_data.use_pattern);
+                }
+            }
+            Msg::ToggleResizeMode(id) => {
+                let index = state.images.iter().position(|d| d.id == id);
+                if let Some(i) = index {
+                    let img_data = &mut state.images[i];
+                    if img_data.use_pattern {
+                        img_data.resize_mode = img_data.resize_mode.toggle();
+                    }
+                    // log!("Msg::ToggleResizeMode", img_data.resize_mode.to_string());
+                }
+            }
+            Msg::ToggleRenderer => {
+                state.renderer = state.renderer.toggle();
+                // log!("Msg::ToggleRenderer", state.renderer.to_string());
+            }
+            Msg::ShowToast(message) => {
+                show_toast(state, message);
+            }
+            Msg::DismissToast(id) => {
+                state.toasts.retain(|toast| toast.id != id);
+            }
+            Msg::SetViewport(width, height) => {
+                state.viewport = (width, height);
+                // log!("Msg::SetViewport", width, height);
+            }
+            Msg::SetAutosave(autosave) => {
+                state.autosave = autosave;
+                // log!("Msg::SetAutosave");
+            }
+            Msg::RestoreScene(scene) => {
+                let inactive_images = state
+                    .boards
+                    .list
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != state.boards.active)
+                    .flat_map(|(_, board)| board.images.iter());
+                for img_data in state.images.iter().chain(inactive_images) {
+                    release_resources(img_data);
+                }
+                for board in scene.boards.list.iter() {
+                    for img_data in board.images.iter() {
+                        object_urls::retain(&img_data.url, &img_data.id);
+                    }
+                }
+                state.boards = scene.boards;
+                state.next_image_id = scene.next_image_id;
+                load_active_board(state);
+                state.autosave = AutosaveStatus::Enabled;
+                // log!("Msg::RestoreScene", state.boards.list.len());
+            }
+            Msg::AddBoard => {
+                let i = state.boards.add();
+                switch_board(state, i);
+                // log!("Msg::AddBoard", i);
+            }
+            Msg::SwitchBoard(id) => {
+                if let Some(i) = state.boards.index_of(id) {
+                    switch_board(state, i);
+                    // log!("Msg::SwitchBoard", id);
+                }
+            }
+            Msg::RenameBoard(id, name) => {
+                let name = name.trim();
+                let index = state.boards.index_of(id);
+                if let (Some(i), false) = (index, name.is_empty()) {
+                    state.boards.list[i].name = name.to_string();
+                    // log!("Msg::RenameBoard", id, name);
+                }
+            }
+            Msg::DeleteBoard(id) => {
+                let index = state.boards.index_of(id);
+                // there is always at least one board
+                if let (Some(i), true) = (index, state.boards.list.len() > 1) {
+                    if i == state.boards.active {
+                        for img_data in state.images.iter() {
+                            release_resources(img_data);
+                        }
+                        state.boards.list.remove(i);
+                        state.boards.active = i.min(state.boards.list.len() - 1);
+                        load_active_board(state);
+                    } else {
+                        let board = state.boards.list.remove(i);
+                        for img_data in board.images.iter() {
+                            release_resources(img_data);
+                        }
+                        if i < state.boards.active {
+                            state.boards.active -= 1;
+                        }
+                    }
+                    // log!("Msg::DeleteBoard", id);
+                }
+            }
+            Msg::SetPixelRatio(pixel_ratio) => {
+                state.pixel_ratio = pixel_ratio;
+                // log!("Msg::SetPixelRatio", pixel_ratio.0);
+            }
+            Msg::NextPlacementPolicy => {
+                state.placement_policy = state.placement_policy.next();
+                // log!("Msg::NextPlacementPolicy", state.placement_policy.to_string());
+            }
+            Msg::SetPlacementValue(value) => {
+                state.placement_policy = state.placement_policy.with_value(value);
+                // log!("Msg::SetPlacementValue", state.placement_policy.to_string());
+            }
+            Msg::NextPageFormat => {
+                state.page_format = state.page_format.next();
+                // log!("Msg::NextPageFormat", state.page_format.to_string());
+            }
+            Msg::AddGuide(guide) => {
+                state.guides.push(guide);
+                state.drag_guide = Some(state.guides.len() - 1);
+                // log!("Msg::AddGuide", state.guides.len());
+            }
+            Msg::DragGuide(i) => {
+                if i < state.guides.len() {
+                    state.drag_guide = Some(i);
+                }
+                // log!("Msg::DragGuide", i);
+            }
+            Msg::NextArtboardPreset => {
+                let preset = state.artboard.preset.next();
+                state.artboard.set_preset(preset);
+                // log!("Msg::NextArtboardPreset", preset.to_string());
+            }
+            Msg::SetArtboardSize(width, height) => {
+                state.artboard.set_size(width, height);
+                // log!("Msg::SetArtboardSize", width, height);
+            }
+            Msg::SetArtboardBackground(background) => {
+                state.artboard.background = background;
+                // log!("Msg::SetArtboardBackground", format!("{:?}", background));
+            }
             Msg::CtrlKeyDown(flag) => {
                 state.ctrl_key_down = flag;
                 // log!("Msg::CtrlKeyDown", flag);
             }
-            Msg::ShiftKeyDown(flag) => {
-                state.shift_key_down = flag;
-                // log!("Msg::ShiftKeyDown", flag);
+            Msg::AltKeyDown(flag) => {
+                state.alt_key_down = flag;
+                // log!("Msg::AltKeyDown", flag);
             }
             Msg::None => (),
         };
+        if changes_scene {
+            state.revision += 1;
+        }
 
         app_state
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a state with one loaded image at 100, 100 of 300 by 200 pixels
+    fn state() -> AppState {
+        let mut state = AppState {
+            next_z_index: 1,
+            next_image_id: 1,
+            ..Default::default()
+        };
+        state.images.push(image("0", 100, 100, 300, 200));
+        state
+    }
+
+    fn image(id: &str, x: i16, y: i16, width: i16, height: i16) -> ImageData {
+        ImageData {
+            id: id.to_string(),
+            x,
+            y,
+            width,
+            height,
+            natural_width: width,
+            natural_height: height,
+            pattern_width: width,
+            pattern_height: height,
+            ratio_wh: width as f32 / height.max(1) as f32,
+            aspect_locked: true,
+            z_index: 1,
+            status: ImageStatus::Loaded,
+            ..Default::default()
+        }
+    }
+
+    /// Applies the messages in order, like the store does
+    fn apply(state: AppState, messages: Vec<Msg>) -> AppState {
+        let state = messages
+            .into_iter()
+            .fold(Rc::new(state), |state, msg| msg.apply(state));
+        Rc::try_unwrap(state).unwrap_or_else(|state| (*state).clone())
+    }
+
+    fn bounding_box(state: &AppState) -> BoundingBox {
+        state.images[0].bounding_box()
+    }
+
+    fn resize(state: AppState, handle: HandleId, dx: i16, dy: i16) -> AppState {
+        let img = state.images[0].bounding_box();
+        let (_, x, y) = handle.get_position(img.width, img.height);
+        let mouse = Position {
+            x: img.x + x + 5,
+            y: img.y + y + 5,
+        };
+        apply(
+            state,
+            vec![
+                Msg::SetActiveHandle(handle, "0".to_string(), 5, 5),
+                Msg::MouseMove(mouse.x + dx, mouse.y + dy),
+                Msg::MouseUp,
+            ],
+        )
+    }
+
+    #[test]
+    fn dragging_an_image_moves_and_selects_it() {
+        let state = apply(
+            state(),
+            vec![
+                Msg::MouseDown(150, 150),
+                Msg::MouseMove(170, 140),
+                Msg::MouseUp,
+            ],
+        );
+        assert!(bounding_box(&state).x == 120 && bounding_box(&state).y == 90);
+        assert_eq!(state.selected, vec!["0".to_string()]);
+        assert_eq!(state.active_image_index, None);
+    }
+
+    #[test]
+    fn dragging_over_an_empty_area_selects_the_images_in_the_marquee() {
+        let mut state = state();
+        state.images.push(image("1", 600, 600, 100, 100));
+        let state = apply(
+            state,
+            vec![Msg::MouseDown(10, 10), Msg::MouseMove(150, 150)],
+        );
+        assert!(state.marquee.is_some());
+        assert_eq!(state.selected, vec!["0".to_string()]);
+        let state = apply(state, vec![Msg::MouseUp]);
+        assert!(state.marquee.is_none());
+        assert_eq!(state.selected, vec!["0".to_string()]);
+    }
+
+    #[test]
+    fn a_locked_image_keeps_its_ratio() {
+        let state = resize(state(), HandleId::BottomRight, 50, 10);
+        assert!(
+            bounding_box(&state)
+                == BoundingBox {
+                    x: 100,
+                    y: 100,
+                    width: 350,
+                    height: 233,
+                }
+        );
+    }
+
+    #[test]
+    fn an_unlocked_image_is_resized_freely() {
+        let mut state = state();
+        state.images[0].aspect_locked = false;
+        let state = resize(state, HandleId::BottomRight, 50, 10);
+        assert!(
+            bounding_box(&state)
+                == BoundingBox {
+                    x: 100,
+                    y: 100,
+                    width: 350,
+                    height: 210,
+                }
+        );
+    }
+
+    #[test]
+    fn the_ctrl_key_inverts_the_aspect_lock() {
+        let mut state = state();
+        state.ctrl_key_down = true;
+        let locked = resize(state.clone(), HandleId::BottomRight, 50, 10);
+        assert_eq!(bounding_box(&locked).height, 210);
+        state.images[0].aspect_locked = false;
+        let unlocked = resize(state, HandleId::BottomRight, 50, 10);
+        assert_eq!(bounding_box(&unlocked).height, 233);
+    }
+
+    #[test]
+    fn the_alt_key_resizes_about_the_centre() {
+        let mut state = state();
+        state.images[0].aspect_locked = false;
+        let state = apply(state, vec![Msg::AltKeyDown(true)]);
+        let state = resize(state, HandleId::MidRight, 20, 0);
+        assert!(
+            bounding_box(&state)
+                == BoundingBox {
+                    x: 80,
+                    y: 100,
+                    width: 340,
+                    height: 200,
+                }
+        );
+    }
+
+    #[test]
+    fn a_free_resize_sets_the_ratio_of_the_next_locked_resize() {
+        // a locked resize of a stretched image keeps the stretched ratio instead of jumping back to the natural ratio
+        let mut state = state();
+        state.ctrl_key_down = true;
+        let mut state = resize(state, HandleId::MidBottom, 0, -100);
+        assert_eq!(bounding_box(&state).height, 100);
+        state.ctrl_key_down = false;
+        let state = resize(state, HandleId::BottomRight, 30, 0);
+        assert_eq!(bounding_box(&state).width, 330);
+        assert_eq!(bounding_box(&state).height, 110);
+    }
+
+    #[test]
+    fn repeated_locked_resizes_do_not_drift() {
+        let mut state = state();
+        state.images[0] = image("0", 100, 100, 301, 97);
+        let ratio = state.images[0].ratio_wh;
+        for step in 0..50 {
+            let delta = if step % 2 == 0 { -7 } else { 5 };
+            state = resize(state, HandleId::BottomRight, delta, 0);
+        }
+        assert_eq!(state.images[0].ratio_wh, ratio);
+        let bb = bounding_box(&state);
+        assert_eq!(bb.width, 301 - 25 * 2);
+        assert_eq!(bb.height, (bb.width as f32 / ratio).round() as i16);
+    }
+
+    #[test]
+    fn every_handle_resizes_a_locked_image_to_the_same_ratio() {
+        for handle in HandleId::into_iter() {
+            let (dir_x, dir_y) = handle.edges();
+            let state = resize(state(), handle.clone(), 30 * dir_x, 30 * dir_y);
+            let bb = bounding_box(&state);
+            let ratio = bb.width as f32 / bb.height as f32;
+            assert!((ratio - 1.5).abs() < 0.01, "{}: {}", handle, bb);
+            assert!(bb.width > 300, "{}: {}", handle, bb);
+        }
+    }
+
+    #[test]
+    fn the_alt_key_on_a_handle_resizes_instead_of_dragging_the_pattern() {
+        let mut state = state();
+        state.images[0].use_pattern = true;
+        state.images[0].aspect_locked = false;
+        let state = apply(
+            state,
+            vec![
+                Msg::AltKeyDown(true),
+                Msg::SetActiveHandle(HandleId::MidRight, "0".to_string(), 5, 5),
+                // the press reaches the stage as well in some renderers
+                Msg::MouseDown(395, 200),
+            ],
+        );
+        assert!(!state.drag_pattern);
+        assert!(state.active_handle == Some(HandleId::MidRight));
+        let (_, x, y) = HandleId::MidRight.get_position(300, 200);
+        let state = apply(
+            state,
+            vec![Msg::MouseMove(100 + x + 25, 100 + y + 5), Msg::MouseUp],
+        );
+        assert!(bounding_box(&state).x == 80 && bounding_box(&state).width == 340);
+        assert_eq!(state.images[0].pattern_x, 0);
+
+        // pressing the image itself drags the origin of the pattern
+        let state = apply(
+            state,
+            vec![
+                Msg::MouseDown(200, 200),
+                Msg::MouseMove(210, 220),
+                Msg::MouseUp,
+            ],
+        );
+        assert!(bounding_box(&state).x == 80 && bounding_box(&state).y == 100);
+        assert!(state.images[0].pattern_x == 10 && state.images[0].pattern_y == 20);
+    }
+
+    #[test]
+    fn dragging_snaps_to_a_guide() {
+        let mut state = state();
+        state.guides.push(Guide::Vertical(405));
+        let state = apply(
+            state,
+            vec![
+                Msg::MouseDown(150, 150),
+                Msg::MouseMove(153, 150),
+                Msg::MouseUp,
+            ],
+        );
+        assert_eq!(bounding_box(&state).x, 105);
+    }
+
+    #[test]
+    fn a_guide_dropped_onto_its_ruler_is_removed() {
+        let state = apply(
+            state(),
+            vec![
+                Msg::AddGuide(Guide::Horizontal(30)),
+                Msg::MouseMove(200, 200),
+            ],
+        );
+        assert!(state.guides == vec![Guide::Horizontal(200)]);
+        let state = apply(state, vec![Msg::MouseUp]);
+        assert_eq!(state.guides.len(), 1);
+        let state = apply(
+            state,
+            vec![Msg::DragGuide(0), Msg::MouseMove(200, 30), Msg::MouseUp],
+        );
+        assert!(state.guides.is_empty());
+        assert_eq!(state.drag_guide, None);
+    }
+
+    #[test]
+    fn setting_the_width_with_the_lock_scales_the_height() {
+        let state = apply(
+            state(),
+            vec![Msg::SetImageProperty(
+                "0".to_string(),
+                ImageProperty::Width,
+                150,
+                true,
+            )],
+        );
+        assert!(bounding_box(&state).width == 150 && bounding_box(&state).height == 100);
+        assert_eq!(state.images[0].pattern_height, 100);
+        let state = apply(
+            state,
+            vec![Msg::SetImageProperty(
+                "0".to_string(),
+                ImageProperty::Height,
+                300,
+                false,
+            )],
+        );
+        assert!(bounding_box(&state).width == 150 && bounding_box(&state).height == 300);
+    }
+
+    #[test]
+    fn setting_the_width_repeatedly_keeps_the_natural_ratio() {
+        let mut state = state();
+        state.images[0] = image("0", 0, 0, 1000, 333);
+        for width in [7, 31, 3, 999, 250, 11, 1000] {
+            state = apply(
+                state,
+                vec![Msg::SetImageProperty(
+                    "0".to_string(),
+                    ImageProperty::Width,
+                    width,
+                    true,
+                )],
+            );
+            let expected = (width as f32 * 333. / 1000.).round().max(1.) as i16;
+            assert_eq!(bounding_box(&state).height, expected, "width {}", width);
+        }
+        assert_eq!(bounding_box(&state).height, 333);
+    }
+
+    #[test]
+    fn resetting_the_ratio_restores_the_natural_ratio() {
+        let mut state = state();
+        state.images[0].height = 50;
+        state.images[0].ratio_wh = 6.;
+        let state = apply(state, vec![Msg::ResetRatio("0".to_string())]);
+        assert_eq!(bounding_box(&state).width, 300);
+        assert_eq!(bounding_box(&state).height, 200);
+        assert_eq!(state.images[0].ratio_wh, 1.5);
+    }
+
+    #[test]
+    fn the_z_index_does_not_drop_below_zero() {
+        let mut state = state();
+        state.active_image_index = Some(0);
+        let state = apply(
+            state,
+            vec![Msg::ImageToBack, Msg::ImageToBack, Msg::ImageToBack],
+        );
+        assert_eq!(state.images[0].z_index, 0);
+        let state = apply(state, vec![Msg::ImageToFront, Msg::ImageToFront]);
+        assert_eq!(state.images[0].z_index, 2);
+        assert_eq!(state.next_z_index, 2);
+    }
+
+    #[test]
+    fn an_image_without_height_can_be_resized_and_edited() {
+        let mut state = state();
+        state.images[0] = image("0", 100, 100, 300, 0);
+        for handle in HandleId::into_iter() {
+            for (ctrl, alt) in [(false, false), (true, false), (false, true), (true, true)] {
+                let state = apply(
+                    state.clone(),
+                    vec![Msg::CtrlKeyDown(ctrl), Msg::AltKeyDown(alt)],
+                );
+                let state = resize(state, handle.clone(), 20, 20);
+                let bb = bounding_box(&state);
+                assert!(bb.width >= 1 && bb.height >= 1, "{}: {}", handle, bb);
+            }
+        }
+        let state = apply(
+            state,
+            vec![
+                Msg::SetImageProperty("0".to_string(), ImageProperty::Width, 100, true),
+                Msg::ResetRatio("0".to_string()),
+            ],
+        );
+        assert_eq!(bounding_box(&state).width, 100);
+        assert!(bounding_box(&state).height >= 1);
+    }
+
+    #[test]
+    fn every_board_keeps_its_own_images() {
+        let state = apply(state(), vec![Msg::AddBoard]);
+        assert!(state.images.is_empty());
+        assert_eq!(state.boards.active, 1);
+        let mut state = state;
+        state.images.push(image("1", 0, 0, 10, 10));
+        let state = apply(state, vec![Msg::SwitchBoard(1)]);
+        assert_eq!(state.images.len(), 1);
+        assert_eq!(state.images[0].id, "0");
+        let state = apply(state, vec![Msg::SwitchBoard(2)]);
+        assert_eq!(state.images[0].id, "1");
+    }
+
+    #[test]
+    fn frames_advance_only_when_their_delay_has_passed() {
+        let mut state = state();
+        state.images[0].frame_delays = vec![100, 50];
+        state.images[0].playing = true;
+        let state = apply(state, vec![Msg::AdvanceFrames(1000.)]);
+        assert_eq!(state.images[0].frame_started, 1000.);
+        // a tick without a frame that is due keeps the state as it is, without copying it
+        let before = Rc::new(state);
+        let after = Msg::AdvanceFrames(1099.).apply(before.clone());
+        assert!(Rc::ptr_eq(&before, &after));
+        let state = apply((*after).clone(), vec![Msg::AdvanceFrames(1100.)]);
+        assert_eq!(state.images[0].frame, 1);
+        let state = apply(state, vec![Msg::AdvanceFrames(1160.)]);
+        assert_eq!(state.images[0].frame, 0);
+    }
+
+    #[test]
+    fn only_changes_to_the_scene_change_the_revision() {
+        let state = apply(
+            state(),
+            vec![
+                Msg::MouseMove(150, 150),
+                Msg::MouseDown(150, 150),
+                Msg::AltKeyDown(true),
+                Msg::ShowToast("saved".to_string()),
+            ],
+        );
+        assert_eq!(state.revision, 0);
+        let state = apply(state, vec![Msg::MouseMove(160, 150), Msg::MouseUp]);
+        assert_eq!(state.revision, 1);
+        let state = apply(state, vec![Msg::AddGuide(Guide::Vertical(5))]);
+        assert_eq!(state.revision, 2);
+    }
+
+    #[test]
+    fn a_scene_has_images_if_any_board_has_images() {
+        // the images of the first board are kept on the board when a new board is added
+        let state = apply(state(), vec![Msg::AddBoard]);
+        assert!(state.images.is_empty());
+        assert!(SavedScene::new(&state).has_images());
+        let state = apply(
+            state,
+            vec![Msg::SwitchBoard(1), Msg::RemoveImage(Some("0".to_string()))],
+        );
+        assert!(!SavedScene::new(&state).has_images());
+    }
+
+    #[test]
+    fn removing_an_image_clears_the_selection() {
+        let state = apply(
+            state(),
+            vec![
+                Msg::MouseDown(150, 150),
+                Msg::MouseUp,
+                Msg::RemoveImage(Some("0".to_string())),
+            ],
+        );
+        assert!(state.images.is_empty());
+        assert!(state.selected.is_empty());
+        assert_eq!(state.hover, None);
+    }
+}
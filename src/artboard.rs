@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{artboard_preset::ArtboardPreset, bounding_box::BoundingBox};
+
+/// The position of the top left corner of the artboard on the stage, just below the header
+pub const ORIGIN: (i16, i16) = (20, 43);
+/// The largest width and height of the artboard
+pub const MAX_SIZE: i16 = 10_000;
+
+/// The fixed size area that is exported; everything outside the artboard is masked on the stage and left out of the
+/// exports. The artboard belongs to a board, like the renderer and the placement policy.
+///
+/// - `preset`&rarr; The preset the size was taken from; with the `Window` preset there is no artboard
+/// - `width`, `height`&rarr; The size of the artboard in css pixels
+/// - `background`&rarr; The background colour as css hex colour, or `None` if the background is transparent
+///
+/// Implements the following functions:
+/// - `set_preset`&rarr; switches to a preset and takes over its size
+/// - `set_size`&rarr; sets a custom size
+/// - `bounding_box`&rarr; returns the area of the artboard on the stage, if there is an artboard
+/// - `shows`&rarr; returns true if (a part of) an image with the given bounding box lies on the artboard
+/// - `background_rgb`&rarr; returns the background colour as red, green and blue components
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Artboard {
+    pub preset: ArtboardPreset,
+    pub width: i16,
+    pub height: i16,
+    pub background: Option<String>,
+}
+
+impl Default for Artboard {
+    fn default() -> Self {
+        Artboard {
+            preset: ArtboardPreset::Window,
+            width: 1080,
+            height: 1080,
+            background: Some("#ffffff".to_string()),
+        }
+    }
+}
+
+impl Artboard {
+    /// Switches to a preset; presets without a fixed size keep the current size
+    pub fn set_preset(&mut self, preset: ArtboardPreset) {
+        self.preset = preset;
+        if let Some((width, height)) = preset.size() {
+            self.width = width;
+            self.height = height;
+        }
+    }
+
+    /// Sets a custom size, the width and the height are kept between 1 and `MAX_SIZE`
+    pub fn set_size(&mut self, width: i16, height: i16) {
+        self.preset = ArtboardPreset::Custom;
+        self.width = width.clamp(1, MAX_SIZE);
+        self.height = height.clamp(1, MAX_SIZE);
+    }
+
+    /// Returns the area of the artboard on the stage, or `None` if the stage is the whole window
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        if self.preset == ArtboardPreset::Window {
+            return None;
+        }
+        Some(BoundingBox {
+            x: ORIGIN.0,
+            y: ORIGIN.1,
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    /// Returns true if (a part of) the bounding box lies on the artboard, or if there is no artboard
+    pub fn shows(&self, bb: &BoundingBox) -> bool {
+        match self.bounding_box() {
+            Some(artboard) => artboard.intersects(bb),
+            None => true,
+        }
+    }
+
+    /// Parses the background colour, a css hex colour like `#ff8000`. Returns `None` if there is no artboard or if the
+    /// background is transparent.
+    pub fn background_rgb(&self) -> Option<(u8, u8, u8)> {
+        self.bounding_box()?;
+        let hex = self.background.as_ref()?.strip_prefix('#')?;
+        if hex.len() != 6 {
+            return None;
+        }
+        let component = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        Some((component(0)?, component(2)?, component(4)?))
+    }
+}
@@ -0,0 +1,61 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// This enum contains the presets of the artboard.
+///
+/// - `Window`&rarr; there is no artboard, the stage is the whole browser window and exports cover all images
+/// - `A4`&rarr; a portrait A4 page at 96 pixels per inch
+/// - `Square`&rarr; 1080 by 1080 pixels, the size of a square social media post
+/// - `FullHd`&rarr; 1920 by 1080 pixels
+/// - `Custom`&rarr; the size that the user has entered
+///
+/// Implements the following functions:
+/// - `to_string`&rarr; prints out a label that can be used in the user interface
+/// - `next`&rarr; returns the next preset, used to cycle through the presets; the custom size is entered instead
+/// - `size`&rarr; returns the width and the height of the preset
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ArtboardPreset {
+    #[default]
+    Window,
+    A4,
+    Square,
+    FullHd,
+    Custom,
+}
+
+/// prints out a label that can be used in the user interface
+impl fmt::Display for ArtboardPreset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = match &self {
+            Self::Window => "window",
+            Self::A4 => "A4",
+            Self::Square => "1080×1080",
+            Self::FullHd => "1920×1080",
+            Self::Custom => "custom",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl ArtboardPreset {
+    /// returns the next preset
+    pub fn next(&self) -> ArtboardPreset {
+        match &self {
+            Self::Window => Self::A4,
+            Self::A4 => Self::Square,
+            Self::Square => Self::FullHd,
+            Self::FullHd | Self::Custom => Self::Window,
+        }
+    }
+
+    /// Returns the width and the height of the preset in css pixels, or `None` if the preset has no fixed size
+    pub fn size(&self) -> Option<(i16, i16)> {
+        match &self {
+            Self::Window | Self::Custom => None,
+            Self::A4 => Some((794, 1123)),
+            Self::Square => Some((1080, 1080)),
+            Self::FullHd => Some((1920, 1080)),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+};
+
+use gloo_console::log;
+use js_sys::{Array, Promise};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, DomException, EventTarget, IdbDatabase, IdbRequest, IdbTransaction, IdbTransactionMode,
+    Response,
+};
+
+use crate::{object_urls, saved_scene::SavedScene};
+
+/// The name and the version of the database
+const DATABASE_NAME: &str = "yew-image-drop";
+const DATABASE_VERSION: u32 = 1;
+/// The store that holds the scene as json, under the key `SCENE_KEY`
+const SCENE_STORE: &str = "scene";
+const SCENE_KEY: &str = "scene";
+/// The store that holds the files that have been dropped or opened, stored by key
+const BLOB_STORE: &str = "blobs";
+
+thread_local! {
+    /// The object urls of the files that are stored in the database, and for every url the key of the file. Files are
+    /// stored once, after that only the scene is saved.
+    static STORED: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+    /// Is set while the scene is being saved, and whether the scene has changed in the meantime, see `save_latest`
+    static SAVING: Cell<bool> = const { Cell::new(false) };
+    static SAVE_REQUESTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Waits until one of the events is fired on the target
+async fn next_event(target: &EventTarget, events: &[&str]) -> Result<(), JsValue> {
+    let mut listener = None;
+    let promise = Promise::new(&mut |resolve, _| {
+        listener = Some(Closure::<dyn FnMut()>::new(move || {
+            let _ = resolve.call0(&JsValue::NULL);
+        }));
+    });
+    let listener = listener.unwrap();
+    for event in events {
+        target.add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())?;
+    }
+    let result = JsFuture::from(promise).await;
+    for event in events {
+        target.remove_event_listener_with_callback(event, listener.as_ref().unchecked_ref())?;
+    }
+    result.map(|_| ())
+}
+
+/// Waits until the request has finished and returns its result
+async fn request_result(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    next_event(request, &["success", "error"]).await?;
+    match request.error()? {
+        Some(error) => Err(error.into()),
+        None => request.result(),
+    }
+}
+
+/// Waits until all requests of the transaction have been committed. If the transaction is aborted, for instance because
+/// the storage quota has been exceeded, the reason is returned as error.
+async fn transaction_complete(transaction: &IdbTransaction) -> Result<(), JsValue> {
+    next_event(transaction, &["complete", "abort"]).await?;
+    match transaction.error() {
+        Some(error) => Err(error.into()),
+        None => Ok(()),
+    }
+}
+
+/// Opens the database, the stores are created when the database is opened for the first time
+async fn open_database() -> Result<IdbDatabase, JsValue> {
+    let factory = web_sys::window()
+        .unwrap()
+        .indexed_db()?
+        .ok_or_else(|| JsValue::from_str("IndexedDB is not available"))?;
+    let request = factory.open_with_u32(DATABASE_NAME, DATABASE_VERSION)?;
+    let on_upgrade = {
+        let request = request.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            if let Ok(database) = request.result() {
+                let database = database.unchecked_into::<IdbDatabase>();
+                let names = database.object_store_names();
+                for store in [SCENE_STORE, BLOB_STORE] {
+                    if !names.contains(store) {
+                        let _ = database.create_object_store(store);
+                    }
+                }
+            }
+        })
+    };
+    request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+    let result = request_result(&request).await;
+    request.set_onupgradeneeded(None);
+    Ok(result?.unchecked_into())
+}
+
+/// Reads the file an object url refers to
+async fn read_blob(url: &str) -> Result<Blob, JsValue> {
+    let window = web_sys::window().unwrap();
+    let response = JsFuture::from(window.fetch_with_str(url))
+        .await?
+        .dyn_into::<Response>()?;
+    JsFuture::from(response.blob()?).await?.dyn_into::<Blob>()
+}
+
+/// Returns true if the error is caused by a full storage
+pub fn is_quota_exceeded(error: &JsValue) -> bool {
+    error
+        .dyn_ref::<DomException>()
+        .map_or(false, |e| e.name() == "QuotaExceededError")
+}
+
+/// Returns true if a scene of a previous session has been saved
+pub async fn has_saved_scene() -> Result<bool, JsValue> {
+    let database = open_database().await?;
+    let transaction = database.transaction_with_str(SCENE_STORE)?;
+    let request = transaction.object_store(SCENE_STORE)?.count()?;
+    let count = request_result(&request).await?;
+    database.close();
+    Ok(count.as_f64().unwrap_or_default() > 0.)
+}
+
+/// Saves the scene that `scene` returns. Saves never run at the same time, otherwise a file could be stored twice: if a
+/// save is running already, the scene is saved again as soon as that save has finished, with the scene at that moment.
+pub async fn save_latest(scene: impl Fn() -> SavedScene) -> Result<(), JsValue> {
+    SAVE_REQUESTED.with(|requested| requested.set(true));
+    if SAVING.with(|saving| saving.replace(true)) {
+        return Ok(());
+    }
+    let result = loop {
+        SAVE_REQUESTED.with(|requested| requested.set(false));
+        let result = save(&scene()).await;
+        if result.is_err() || !SAVE_REQUESTED.with(|requested| requested.get()) {
+            break result;
+        }
+    };
+    SAVING.with(|saving| saving.set(false));
+    result
+}
+
+/// Saves the scene. The files of images that have been dropped or opened are read from their object urls and stored
+/// along with the scene, files that are stored already are skipped and files that are no longer used are deleted. A
+/// scene without images is deleted instead of saved, so the next session doesn't ask to restore an empty scene.
+async fn save(scene: &SavedScene) -> Result<(), JsValue> {
+    if !scene.has_images() {
+        return clear().await;
+    }
+    let mut scene = scene.clone();
+    let mut used = HashSet::new();
+    let mut new_blobs = Vec::new();
+    for img in scene
+        .boards
+        .list
+        .iter_mut()
+        .flat_map(|board| board.images.iter_mut())
+    {
+        if !img.url.starts_with("blob:") {
+            continue;
+        }
+        let key = match STORED.with(|stored| stored.borrow().get(&img.url).cloned()) {
+            Some(key) => key,
+            None if new_blobs.iter().any(|(url, _)| *url == img.url) => img.url.clone(),
+            None => {
+                new_blobs.push((img.url.clone(), read_blob(&img.url).await?));
+                img.url.clone()
+            }
+        };
+        used.insert(img.url.clone());
+        img.url = key;
+    }
+    let json = serde_json::to_string(&scene).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    // the files are read before the transaction is started, a transaction commits as soon as it has nothing to do
+    let database = open_database().await?;
+    let stores = Array::of2(&SCENE_STORE.into(), &BLOB_STORE.into());
+    let transaction =
+        database.transaction_with_str_sequence_and_mode(&stores, IdbTransactionMode::Readwrite)?;
+    let blob_store = transaction.object_store(BLOB_STORE)?;
+    for (url, blob) in new_blobs.iter() {
+        blob_store.put_with_key(blob, &url.into())?;
+    }
+    let unused: Vec<(String, String)> = STORED.with(|stored| {
+        stored
+            .borrow()
+            .iter()
+            .filter(|(url, _)| !used.contains(*url))
+            .map(|(url, key)| (url.clone(), key.clone()))
+            .collect()
+    });
+    for (_, key) in unused.iter() {
+        blob_store.delete(&key.into())?;
+    }
+    transaction
+        .object_store(SCENE_STORE)?
+        .put_with_key(&json.into(), &SCENE_KEY.into())?;
+    let result = transaction_complete(&transaction).await;
+    database.close();
+    result?;
+
+    STORED.with(|stored| {
+        let mut stored = stored.borrow_mut();
+        for (url, _) in unused {
+            stored.remove(&url);
+        }
+        for (url, _) in new_blobs {
+            stored.insert(url.clone(), url);
+        }
+    });
+    Ok(())
+}
+
+/// Loads the scene of the previous session. New object urls are created for the stored files; images whose file is
+/// missing keep their url, so they are shown as broken images. Returns `None` if no scene has been saved or if the scene
+/// can't be read, for instance because it has been saved by an older version of the app.
+pub async fn load() -> Result<Option<SavedScene>, JsValue> {
+    let database = open_database().await?;
+    let stores = Array::of2(&SCENE_STORE.into(), &BLOB_STORE.into());
+    let transaction = database.transaction_with_str_sequence(&stores)?;
+    // all requests are made at once, a transaction commits as soon as it has nothing to do
+    let scene_request = transaction
+        .object_store(SCENE_STORE)?
+        .get(&SCENE_KEY.into())?;
+    let blob_store = transaction.object_store(BLOB_STORE)?;
+    let keys_request = blob_store.get_all_keys()?;
+    let blobs_request = blob_store.get_all()?;
+    let json = request_result(&scene_request).await?;
+    let keys = Array::from(&request_result(&keys_request).await?);
+    let blobs = Array::from(&request_result(&blobs_request).await?);
+    database.close();
+
+    let json = match json.as_string() {
+        Some(json) => json,
+        None => return Ok(None),
+    };
+    let mut scene = match serde_json::from_str::<SavedScene>(&json) {
+        Ok(scene) => scene,
+        Err(e) => {
+            log!("error read saved scene", e.to_string());
+            return Ok(None);
+        }
+    };
+
+    // urls are only created for the files that images refer to
+    let used: HashSet<&String> = scene
+        .boards
+        .list
+        .iter()
+        .flat_map(|board| board.images.iter())
+        .map(|img| &img.url)
+        .collect();
+    let mut urls: HashMap<String, String> = HashMap::new();
+    for (key, blob) in keys.iter().zip(blobs.iter()) {
+        if let (Some(key), Ok(blob)) = (key.as_string(), blob.dyn_into::<Blob>()) {
+            if !used.contains(&key) {
+                // no image uses the key as url, so the file is deleted with the next save
+                STORED.with(|stored| stored.borrow_mut().insert(key.clone(), key));
+                continue;
+            }
+            // the scene isn't restored, so the urls that have been created already are never used
+            let url = match object_urls::create(&blob, &key) {
+                Ok(url) => url,
+                Err(e) => {
+                    for url in urls.values() {
+                        STORED.with(|stored| stored.borrow_mut().remove(url));
+                        object_urls::revoke_unused(url);
+                    }
+                    return Err(e);
+                }
+            };
+            STORED.with(|stored| stored.borrow_mut().insert(url.clone(), key.clone()));
+            urls.insert(key, url);
+        }
+    }
+    for img in scene
+        .boards
+        .list
+        .iter_mut()
+        .flat_map(|board| board.images.iter_mut())
+    {
+        if let Some(url) = urls.get(&img.url) {
+            img.url = url.clone();
+        }
+    }
+    Ok(Some(scene))
+}
+
+/// Deletes the saved scene and all stored files, used when the user doesn't want to restore the previous session
+pub async fn clear() -> Result<(), JsValue> {
+    let database = open_database().await?;
+    let stores = Array::of2(&SCENE_STORE.into(), &BLOB_STORE.into());
+    let transaction =
+        database.transaction_with_str_sequence_and_mode(&stores, IdbTransactionMode::Readwrite)?;
+    transaction.object_store(SCENE_STORE)?.clear()?;
+    transaction.object_store(BLOB_STORE)?.clear()?;
+    let result = transaction_complete(&transaction).await;
+    database.close();
+    STORED.with(|stored| stored.borrow_mut().clear());
+    result
+}
@@ -0,0 +1,16 @@
+/// This enum contains the stages of saving the scene automatically, see `autosave`.
+///
+/// - `Checking`&rarr; the app has just started and checks whether a scene of a previous session has been saved
+/// - `Prompt`&rarr; a scene of a previous session has been found, the user is asked whether to restore it. Until the user
+/// has decided the scene is not saved, so the previous session isn't overwritten
+/// - `Enabled`&rarr; the scene is saved shortly after every change
+/// - `Disabled`&rarr; the scene can't be saved, for instance because the storage quota has been exceeded or because the
+/// browser doesn't allow the app to use IndexedDB
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum AutosaveStatus {
+    #[default]
+    Checking,
+    Prompt,
+    Enabled,
+    Disabled,
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::ImageData, artboard::Artboard, guide::Guide, placement_policy::PlacementPolicy,
+    renderer::Renderer,
+};
+
+/// A named document with its own images and settings, the user switches between boards in the header.
+///
+/// - `id`&rarr; Identifies the board, ids are never reused
+/// - `name`&rarr; The name the user has given the board
+/// - `images`, `next_z_index`, `renderer`, `placement_policy`, `artboard`, `guides`&rarr; The images and settings of the board. The images and
+/// settings of the active board are kept in `AppState` itself, they are only copied to the board when the user switches
+/// to another board and when the scene is saved.
+#[derive(Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Board {
+    pub id: usize,
+    pub name: String,
+    pub images: Vec<ImageData>,
+    pub next_z_index: i16,
+    pub renderer: Renderer,
+    pub placement_policy: PlacementPolicy,
+    /// boards that were saved before there were artboards have none
+    #[serde(default)]
+    pub artboard: Artboard,
+    #[serde(default)]
+    pub guides: Vec<Guide>,
+}
+
+impl Board {
+    /// Creates an empty board that is named after its id
+    pub fn new(id: usize) -> Board {
+        Board {
+            id,
+            name: format!("board {}", id),
+            ..Default::default()
+        }
+    }
+}
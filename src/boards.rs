@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+
+/// The boards the user works on, there is always at least one board.
+///
+/// - `list`&rarr; All boards in the order they have been created
+/// - `active`&rarr; The index of the board that is shown
+/// - `next_id`&rarr; The id of the next board that is created
+///
+/// Implements the following functions:
+/// - `add`&rarr; creates a new board and returns its index
+/// - `index_of`&rarr; returns the index of the board with the given id
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Boards {
+    pub list: Vec<Board>,
+    pub active: usize,
+    pub next_id: usize,
+}
+
+impl Default for Boards {
+    fn default() -> Self {
+        Boards {
+            list: vec![Board::new(1)],
+            active: 0,
+            next_id: 2,
+        }
+    }
+}
+
+impl Boards {
+    /// creates a new board and returns its index
+    pub fn add(&mut self) -> usize {
+        self.list.push(Board::new(self.next_id));
+        self.next_id += 1;
+        self.list.len() - 1
+    }
+
+    /// returns the index of the board with the given id
+    pub fn index_of(&self, id: usize) -> Option<usize> {
+        self.list.iter().position(|board| board.id == id)
+    }
+}
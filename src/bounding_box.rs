@@ -1,5 +1,7 @@
 use std::fmt;
 
+use crate::position::Position;
+
 /// Struct that stores the bounding box of the image. The term isn't completely correct because it only stores
 /// x, y, width and height.
 ///
@@ -25,3 +27,43 @@ impl fmt::Display for BoundingBox {
         write!(f, "{}", str)
     }
 }
+
+impl BoundingBox {
+    /// returns true if the position lies within the bounding box
+    pub fn contains(&self, position: Position) -> bool {
+        position.x >= self.x
+            && position.x < self.x + self.width
+            && position.y >= self.y
+            && position.y < self.y + self.height
+    }
+
+    /// returns true if the bounding boxes overlap
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+
+    /// returns the smallest bounding box that contains both bounding boxes
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        BoundingBox {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+
+    /// returns the bounding box grown by `margin` pixels on every side
+    pub fn inflate(&self, margin: i16) -> BoundingBox {
+        BoundingBox {
+            x: self.x - margin,
+            y: self.y - margin,
+            width: self.width + 2 * margin,
+            height: self.height + 2 * margin,
+        }
+    }
+}
@@ -1,27 +1,95 @@
-use crate::app_state::Msg;
+use crate::app_state::{AppState, Msg};
+use crate::components::artboard_frame::ArtboardFrame;
+use crate::components::autosave::Autosave;
 use crate::components::drag_and_drop::UseDrop;
+use crate::components::guides::Guides;
 use crate::components::header::Header;
 use crate::components::images::Images;
+use crate::components::inspector::Inspector;
+use crate::components::marquee::Marquee;
+use crate::components::rulers::Rulers;
+use crate::components::scene::Scene;
+use crate::components::toasts::Toasts;
+use crate::pixel_ratio::PixelRatio;
+use crate::renderer::Renderer;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::Element;
 use yew::prelude::*;
-use yew_hooks::use_event_with_window;
-use yewdux::use_dispatch;
+use yew_hooks::{use_event_with_window, use_interval, use_window_size};
+use yewdux::{use_dispatch, use_selector, Dispatch};
 
-/// 1. Registers user input event listener that need to be handled on document level (mouseup, mousemove, keydown, keyup)
-/// 2. Renders container div that holds the Yew app
+/// The interval in milliseconds at which playing animations are checked for the next frame
+const FRAME_INTERVAL: u32 = 16;
+
+/// returns true if the target of the mouse event is the header, a ruler, a guide or the inspector, or one of their
+/// children; these handle the mouse themselves
+fn is_on_controls(e: &MouseEvent) -> bool {
+    e.target()
+        .and_then(|target| target.dyn_into::<Element>().ok())
+        .and_then(|element| {
+            element
+                .closest("header, .ruler, .guide, .inspector")
+                .ok()
+                .flatten()
+        })
+        .is_some()
+}
+
+/// Updates the state of the `ctrl` and `alt` keys from a mouse event. Key events alone miss a key that is released
+/// while the window doesn't have the focus, for instance after alt+tab.
+fn sync_modifiers(dispatch: &Dispatch<AppState>, e: &MouseEvent) {
+    let state = dispatch.get();
+    if state.ctrl_key_down != e.ctrl_key() {
+        dispatch.apply(Msg::CtrlKeyDown(e.ctrl_key()));
+    }
+    if state.alt_key_down != e.alt_key() {
+        dispatch.apply(Msg::AltKeyDown(e.alt_key()));
+    }
+}
+
+/// returns true if the user types in a form field, the keys are then not used as shortcuts
+fn is_typing(e: &KeyboardEvent) -> bool {
+    e.target()
+        .and_then(|target| target.dyn_into::<Element>().ok())
+        .is_some_and(|element| matches!(element.tag_name().as_str(), "INPUT" | "SELECT"))
+}
+
+/// 1. Registers user input event listener that need to be handled on document level (mousedown, mouseup, mousemove, dblclick,
+/// keydown, keyup, blur). Mouse down and double click events are hit tested in the reducer to find out which image has been
+/// clicked. The state of the `ctrl` and `alt` keys is taken from the mouse events as well, and reset when the window loses
+/// the focus.
+/// 2. Keeps track of the size of the window, so that new images can be placed inside the visible area, and of the device
+/// pixel ratio, so that the canvases are drawn at the resolution of the display. Advances the frames of animated images
+/// 3. Renders container div that holds the Yew app, the images are rendered either as html elements or on a single canvas
 #[function_component(App)]
 pub fn create() -> Html {
     let dispatch = use_dispatch();
+    let renderer = use_selector(|state: &AppState| state.renderer);
 
     {
         let dis = dispatch.clone();
         use_event_with_window("mousemove", move |e: MouseEvent| {
             e.prevent_default();
+            sync_modifiers(&dis, &e);
             let x = e.client_x() as i16;
             let y = e.client_y() as i16;
             dis.apply(Msg::MouseMove(x, y));
         });
     }
 
+    {
+        let dis = dispatch.clone();
+        use_event_with_window("mousedown", move |e: MouseEvent| {
+            sync_modifiers(&dis, &e);
+            if !is_on_controls(&e) {
+                let x = e.client_x() as i16;
+                let y = e.client_y() as i16;
+                dis.apply(Msg::MouseDown(x, y));
+            }
+        });
+    }
+
     {
         let dis = dispatch.clone();
         use_event_with_window("mouseup", move |_e: MouseEvent| {
@@ -29,29 +97,60 @@ pub fn create() -> Html {
         });
     }
 
+    {
+        let dis = dispatch.clone();
+        use_event_with_window("dblclick", move |e: MouseEvent| {
+            if !is_on_controls(&e) {
+                let x = e.client_x() as i16;
+                let y = e.client_y() as i16;
+                dis.apply(Msg::DoubleClick(x, y));
+            }
+        });
+    }
+
     {
         let dis = dispatch.clone();
         use_event_with_window("keydown", move |e: KeyboardEvent| {
-            let msg = if KeyboardEvent::ctrl_key(&e) {
-                Msg::CtrlKeyDown(true)
-            } else if KeyboardEvent::shift_key(&e) {
-                Msg::ShiftKeyDown(true)
-            } else {
-                Default::default()
-            };
-            dis.apply(msg);
+            // pressing alt on its own focuses the menu bar in some browsers, shortcuts with alt and typed characters
+            // like alt+e for € are left to the browser
+            if KeyboardEvent::key(&e) == "Alt" && !is_typing(&e) {
+                e.prevent_default();
+            }
+            // both keys can be held down at the same time
+            if KeyboardEvent::ctrl_key(&e) {
+                dis.apply(Msg::CtrlKeyDown(true));
+            }
+            if KeyboardEvent::alt_key(&e) {
+                dis.apply(Msg::AltKeyDown(true));
+            }
         });
     }
 
     {
         let dis = dispatch.clone();
         use_event_with_window("keypress", move |e: KeyboardEvent| {
-            let msg = if KeyboardEvent::key(&e) == "Delete" {
+            let msg = if is_typing(&e) {
+                Default::default()
+            } else if KeyboardEvent::key(&e) == "Delete" {
                 Msg::RemoveImage(None)
             } else if KeyboardEvent::key(&e) == "+" {
                 Msg::ImageToFront
             } else if KeyboardEvent::key(&e) == "-" {
                 Msg::ImageToBack
+            } else if KeyboardEvent::key(&e) == "r" {
+                Msg::RotatePattern(15)
+            } else if KeyboardEvent::key(&e) == "R" {
+                Msg::RotatePattern(-15)
+            } else if KeyboardEvent::key(&e) == "x" {
+                Msg::NextPatternRepeat
+            } else if KeyboardEvent::key(&e) == "m" {
+                Msg::TogglePatternMirror
+            } else if KeyboardEvent::key(&e) == "p" {
+                Msg::TogglePlayback(None)
+            } else if KeyboardEvent::key(&e) == "," {
+                Msg::StepFrame(-1)
+            } else if KeyboardEvent::key(&e) == "." {
+                Msg::StepFrame(1)
             } else {
                 Default::default()
             };
@@ -64,8 +163,8 @@ pub fn create() -> Html {
         use_event_with_window("keyup", move |e: KeyboardEvent| {
             let msg = if KeyboardEvent::key(&e) == "Control" {
                 Msg::CtrlKeyDown(false)
-            } else if KeyboardEvent::key(&e) == "Shift" {
-                Msg::ShiftKeyDown(false)
+            } else if KeyboardEvent::key(&e) == "Alt" {
+                Msg::AltKeyDown(false)
             } else {
                 Default::default()
             };
@@ -73,12 +172,89 @@ pub fn create() -> Html {
         });
     }
 
+    {
+        // the key up events are sent to another window while the window doesn't have the focus
+        let dis = dispatch.clone();
+        use_event_with_window("blur", move |_e: FocusEvent| {
+            dis.apply(Msg::CtrlKeyDown(false));
+            dis.apply(Msg::AltKeyDown(false));
+        });
+    }
+
+    {
+        let dis = dispatch.clone();
+        let window_size = use_window_size();
+        use_effect_with(window_size, move |(width, height)| {
+            dis.apply(Msg::SetViewport(*width as i16, *height as i16));
+        });
+    }
+
+    {
+        // there is no event for changes of the device pixel ratio; instead a media query that matches the current ratio
+        // is watched, as soon as it stops matching the ratio is read again and a new query is watched
+        let dis = dispatch.clone();
+        let pixel_ratio = use_selector(|state: &AppState| state.pixel_ratio);
+        use_effect_with(*pixel_ratio, move |pixel_ratio| {
+            let current = PixelRatio::current();
+            if current != *pixel_ratio {
+                dis.apply(Msg::SetPixelRatio(current));
+            }
+            let query = web_sys::window()
+                .unwrap()
+                .match_media(&current.media_query())
+                .ok()
+                .flatten();
+            let listener = Closure::<dyn FnMut()>::new(move || {
+                dis.apply(Msg::SetPixelRatio(PixelRatio::current()));
+            });
+            if let Some(query) = &query {
+                let _ = query
+                    .add_event_listener_with_callback("change", listener.as_ref().unchecked_ref());
+            }
+            move || {
+                if let Some(query) = &query {
+                    let _ = query.remove_event_listener_with_callback(
+                        "change",
+                        listener.as_ref().unchecked_ref(),
+                    );
+                }
+            }
+        });
+    }
+
+    {
+        // the frames of animated images are advanced as long as at least one animation is playing
+        let dis = dispatch.clone();
+        let is_playing = use_selector(|state: &AppState| {
+            state
+                .images
+                .iter()
+                .any(|img| img.playing && !img.frame_delays.is_empty())
+        });
+        let millis = if *is_playing { FRAME_INTERVAL } else { 0 };
+        use_interval(
+            move || dis.apply(Msg::AdvanceFrames(js_sys::Date::now())),
+            millis,
+        );
+    }
+
     // log!("render App");
 
     html! {
       <UseDrop>
           <Header />
-          <Images />
+          <ArtboardFrame />
+          if *renderer == Renderer::Canvas {
+            <Scene />
+          } else {
+            <Images />
+          }
+          <Guides />
+          <Rulers />
+          <Marquee />
+          <Inspector />
+          <Toasts />
+          <Autosave />
       </UseDrop>
     }
 }
@@ -0,0 +1,40 @@
+// use gloo_console::log;
+use yew::{function_component, html, Html};
+use yewdux::use_selector;
+
+use crate::{absolute_style::AbsoluteStyle, app_state::AppState};
+
+/// Renders the background of the artboard below the images and a mask over the stage outside the artboard, so that
+/// the stage shows what is exported. The mask lets the mouse events through: images that stick out of the artboard can
+/// still be dragged and resized. A transparent background is shown as a checkerboard.
+#[function_component(ArtboardFrame)]
+pub fn create() -> Html {
+    let artboard = use_selector(|state: &AppState| state.artboard.clone());
+
+    // log!("render ArtboardFrame");
+
+    match artboard.bounding_box() {
+        Some(bb) => {
+            let style = AbsoluteStyle {
+                x: bb.x,
+                y: bb.y,
+                width: Some(bb.width),
+                height: Some(bb.height),
+                z_index: None,
+            }
+            .to_string();
+            let (class, background_style) = match &artboard.background {
+                Some(color) => (
+                    "artboard-background",
+                    format!("{}background-color:{};", style, color),
+                ),
+                None => ("artboard-background artboard-transparent", style.clone()),
+            };
+            html! { <>
+              <div class={class} style={background_style} />
+              <div class="artboard-mask" style={style} />
+            </> }
+        }
+        None => html! {},
+    }
+}
@@ -0,0 +1,76 @@
+// use gloo_console::log;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yewdux::{use_dispatch, use_selector};
+
+use crate::{
+    app_state::{AppState, Msg},
+    artboard::MAX_SIZE,
+    artboard_preset::ArtboardPreset,
+};
+
+/// The colour that a transparent background gets when the user switches back to a coloured background
+const DEFAULT_BACKGROUND: &str = "#ffffff";
+
+/// Renders the settings of the artboard of the active board: a button that cycles through the presets, see
+/// `ArtboardPreset`, and, if there is an artboard, the inputs for a custom size and the background colour.
+#[function_component(ArtboardSwitch)]
+pub fn create() -> Html {
+    let dispatch = use_dispatch();
+    let artboard = use_selector(|state: &AppState| state.artboard.clone());
+    let (width, height) = (artboard.width, artboard.height);
+
+    let on_next = dispatch.apply_callback(|_: MouseEvent| Msg::NextArtboardPreset);
+
+    // a value that isn't a number keeps the current size
+    let on_width = dispatch.apply_callback(move |e: Event| {
+        let input = e.target_unchecked_into::<HtmlInputElement>();
+        match input.value().parse::<i16>() {
+            Ok(width) => Msg::SetArtboardSize(width, height),
+            Err(_) => Msg::None,
+        }
+    });
+    let on_height = dispatch.apply_callback(move |e: Event| {
+        let input = e.target_unchecked_into::<HtmlInputElement>();
+        match input.value().parse::<i16>() {
+            Ok(height) => Msg::SetArtboardSize(width, height),
+            Err(_) => Msg::None,
+        }
+    });
+
+    let on_color = dispatch.apply_callback(|e: InputEvent| {
+        let input = e.target_unchecked_into::<HtmlInputElement>();
+        Msg::SetArtboardBackground(Some(input.value()))
+    });
+
+    let on_toggle_transparent = {
+        let is_transparent = artboard.background.is_none();
+        dispatch.apply_callback(move |_: MouseEvent| {
+            Msg::SetArtboardBackground(if is_transparent {
+                Some(DEFAULT_BACKGROUND.to_string())
+            } else {
+                None
+            })
+        })
+    };
+
+    // log!("render ArtboardSwitch");
+
+    html! { <>
+      <div class="header-button" onclick={on_next}>
+        {"artboard: "}{artboard.preset.to_string()}
+      </div>
+      if artboard.preset != ArtboardPreset::Window {
+        <input class="header-input" type="number" min="1" max={MAX_SIZE.to_string()}
+          value={width.to_string()} onchange={on_width} />
+        <input class="header-input" type="number" min="1" max={MAX_SIZE.to_string()}
+          value={height.to_string()} onchange={on_height} />
+        if let Some(background) = &artboard.background {
+          <input class="header-color" type="color" value={background.clone()} oninput={on_color} />
+        }
+        <div class="header-button" onclick={on_toggle_transparent}>
+          {if artboard.background.is_none() { "transparent" } else { "filled" }}
+        </div>
+      }
+    </> }
+}
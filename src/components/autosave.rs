@@ -0,0 +1,132 @@
+use gloo_console::log;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use yew_hooks::use_debounce_effect_with_deps;
+use yewdux::{use_dispatch, use_selector, Dispatch};
+
+use crate::{
+    app_state::{AppState, Msg},
+    autosave::{self, is_quota_exceeded},
+    autosave_status::AutosaveStatus,
+    saved_scene::SavedScene,
+};
+
+/// The number of milliseconds after the last change at which the scene is saved
+const SAVE_DELAY: u32 = 1000;
+
+/// Saves the scene and disables saving if that fails, so the user is told only once. The scene is copied from the
+/// state when the save starts, not on every change.
+fn save(dispatch: Dispatch<AppState>) {
+    spawn_local(async move {
+        let scene = || SavedScene::new(&dispatch.get());
+        if let Err(e) = autosave::save_latest(scene).await {
+            log!("error save scene", &e);
+            let message = if is_quota_exceeded(&e) {
+                "storage is full, the scene is no longer saved automatically"
+            } else {
+                "the scene can't be saved automatically"
+            };
+            dispatch.apply(Msg::ShowToast(message.to_string()));
+            dispatch.apply(Msg::SetAutosave(AutosaveStatus::Disabled));
+        }
+    });
+}
+
+/// Saves the scene to IndexedDB and restores the scene of the previous session:
+///
+/// 1. On startup checks whether a scene has been saved. If so, the user is asked whether to restore it; until the user
+/// has decided the scene isn't saved, so the previous session isn't overwritten.
+/// 2. As long as autosave is enabled, the scene is saved one second after the last change, see `autosave`. Changes
+/// that only matter during a session, like the position of the mouse, don't change the revision of the state, see
+/// `Msg::changes_scene`.
+#[function_component(Autosave)]
+pub fn create() -> Html {
+    let dispatch = use_dispatch();
+    let status = use_selector(|state: &AppState| state.autosave);
+    let revision = use_selector(|state: &AppState| state.revision);
+
+    {
+        let dispatch = dispatch.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                let status = match autosave::has_saved_scene().await {
+                    Ok(true) => AutosaveStatus::Prompt,
+                    Ok(false) => AutosaveStatus::Enabled,
+                    Err(e) => {
+                        log!("error open database", e);
+                        AutosaveStatus::Disabled
+                    }
+                };
+                dispatch.apply(Msg::SetAutosave(status));
+            });
+        });
+    }
+
+    {
+        let dispatch = dispatch.clone();
+        let is_enabled = *status == AutosaveStatus::Enabled;
+        use_debounce_effect_with_deps(
+            move || {
+                if is_enabled {
+                    save(dispatch);
+                }
+            },
+            SAVE_DELAY,
+            (*revision, *status),
+        );
+    }
+
+    let on_restore = {
+        let dispatch = dispatch.clone();
+        Callback::from(move |_: MouseEvent| {
+            let dispatch = dispatch.clone();
+            spawn_local(async move {
+                match autosave::load().await {
+                    Ok(Some(scene)) => dispatch.apply(Msg::RestoreScene(scene)),
+                    Ok(None) => dispatch.apply(Msg::SetAutosave(AutosaveStatus::Enabled)),
+                    Err(e) => {
+                        log!("error load scene", e);
+                        dispatch.apply(Msg::ShowToast(
+                            "the previous session can't be restored".to_string(),
+                        ));
+                        dispatch.apply(Msg::SetAutosave(AutosaveStatus::Enabled));
+                    }
+                }
+            });
+        })
+    };
+
+    let on_discard = {
+        let dispatch = dispatch.clone();
+        Callback::from(move |_: MouseEvent| {
+            let dispatch = dispatch.clone();
+            spawn_local(async move {
+                let status = match autosave::clear().await {
+                    Ok(()) => AutosaveStatus::Enabled,
+                    Err(e) => {
+                        log!("error clear scene", e);
+                        AutosaveStatus::Disabled
+                    }
+                };
+                dispatch.apply(Msg::SetAutosave(status));
+            });
+        })
+    };
+
+    // clicking the prompt should not start a selection on the stage
+    let on_pointer_down = Callback::from(|e: MouseEvent| {
+        e.stop_immediate_propagation();
+    });
+
+    // log!("render Autosave");
+
+    html! {
+      if *status == AutosaveStatus::Prompt {
+        <div class="restore-prompt" onmousedown={on_pointer_down}>
+          <span>{"restore previous session?"}</span>
+          <div class="header-button" onclick={on_restore}>{"restore"}</div>
+          <div class="header-button" onclick={on_discard}>{"discard"}</div>
+        </div>
+      }
+    }
+}
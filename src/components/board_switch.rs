@@ -0,0 +1,72 @@
+// use gloo_console::log;
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+use yewdux::{use_dispatch, use_selector};
+
+use crate::app_state::{AppState, Msg};
+
+/// Renders the list of boards and the buttons to create, rename and delete a board. Selecting a board in the list shows
+/// the images and settings of that board, see `Board`. The last board can't be deleted.
+#[function_component(BoardSwitch)]
+pub fn create() -> Html {
+    let dispatch = use_dispatch();
+    let boards = use_selector(|state: &AppState| {
+        let names: Vec<(usize, String)> = state
+            .boards
+            .list
+            .iter()
+            .map(|board| (board.id, board.name.clone()))
+            .collect();
+        let active = names[state.boards.active].clone();
+        (names, active)
+    });
+    let (names, (active_id, active_name)) = (*boards).clone();
+
+    let on_change = dispatch.apply_callback(|e: Event| {
+        let select = e.target_unchecked_into::<HtmlSelectElement>();
+        match select.value().parse::<usize>() {
+            Ok(id) => Msg::SwitchBoard(id),
+            Err(_) => Msg::None,
+        }
+    });
+
+    let on_add = dispatch.apply_callback(|_: MouseEvent| Msg::AddBoard);
+
+    let on_rename = {
+        let active_name = active_name.clone();
+        dispatch.apply_callback(move |_: MouseEvent| {
+            let window = web_sys::window().unwrap();
+            match window.prompt_with_message_and_default("Name of the board", &active_name) {
+                Ok(Some(name)) => Msg::RenameBoard(active_id, name),
+                _ => Msg::None,
+            }
+        })
+    };
+
+    let on_delete = dispatch.apply_callback(move |_: MouseEvent| {
+        let window = web_sys::window().unwrap();
+        let message = format!("Delete \"{}\" and all of its images?", active_name);
+        match window.confirm_with_message(&message) {
+            Ok(true) => Msg::DeleteBoard(active_id),
+            _ => Msg::None,
+        }
+    });
+
+    // log!("render BoardSwitch");
+
+    html! { <>
+      <select class="header-select" onchange={on_change}>
+        {
+          names.iter().map(|(id, name)| html! {
+            <option key={*id} value={id.to_string()} selected={*id == active_id}>{name.clone()}</option>
+          }).collect::<Html>()
+        }
+      </select>
+      <div class="header-button" onclick={on_add}>{"new"}</div>
+      <div class="header-button" onclick={on_rename}>{"rename"}</div>
+      if names.len() > 1 {
+        <div class="header-button" onclick={on_delete}>{"delete"}</div>
+      }
+    </>
+    }
+}
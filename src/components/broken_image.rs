@@ -0,0 +1,49 @@
+// use gloo_console::log;
+use yew::prelude::*;
+use yewdux::use_dispatch;
+
+use crate::app_state::Msg;
+
+#[derive(Clone, Properties, PartialEq)]
+pub struct BrokenImageProps {
+    pub image_id: String,
+}
+
+/// Renders the placeholder of an image that could not be loaded. The placeholder fills its container and offers two
+/// buttons:
+///
+/// - `retry`&rarr; loads the image again, see `Msg::RetryImage`
+/// - `remove`&rarr; removes the image from the scene
+///
+/// Like the buttons of the `ImageToolbar` the buttons stop the propagation of the mouse down event so that clicking a
+/// button doesn't start dragging the placeholder.
+#[function_component(BrokenImage)]
+pub fn create(BrokenImageProps { image_id }: &BrokenImageProps) -> Html {
+    let dispatch = use_dispatch();
+
+    let on_retry = {
+        let image_id = image_id.to_owned();
+        dispatch.apply_callback(move |_: MouseEvent| Msg::RetryImage(image_id.clone()))
+    };
+
+    let on_remove = {
+        let image_id = image_id.to_owned();
+        dispatch.apply_callback(move |_: MouseEvent| Msg::RemoveImage(Some(image_id.clone())))
+    };
+
+    let on_pointer_down = Callback::from(|e: MouseEvent| {
+        e.stop_immediate_propagation();
+    });
+
+    // log!("render BrokenImage", image_id.clone());
+
+    html! {
+      <div class="image-broken">
+        <div class="image-broken-label">{"image could not be loaded"}</div>
+        <div class="image-broken-buttons" onmousedown={on_pointer_down}>
+          <div class="image-toolbar-button" onclick={on_retry}>{"retry"}</div>
+          <div class="image-toolbar-button" onclick={on_remove}>{"remove"}</div>
+        </div>
+      </div>
+    }
+}
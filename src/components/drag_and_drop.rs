@@ -1,12 +1,17 @@
 use std::borrow::Borrow;
 
 // use gloo_console::log;
-use web_sys::Url;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::File;
 use yew::prelude::*;
 use yew_hooks::prelude::*;
 use yewdux::{use_dispatch, use_selector};
 
-use crate::app_state::{AppState, Msg};
+use crate::{
+    app_state::{AppState, Msg},
+    import::{import_dropped_content, import_files},
+    position::Position,
+};
 
 #[derive(Properties, PartialEq)]
 pub struct DropProps {
@@ -16,6 +21,13 @@ pub struct DropProps {
 
 /// Drag and drop component. The whole document is a drop area.
 ///
+/// Dropped files are checked by the `import` module, files that can't be imported are reported to the user. Besides files
+/// you can drop urls, html snippets and images that are dragged from another browser tab; the image urls are extracted
+/// from the dropped content by the `import` module as well.
+///
+/// The dropped images are placed at the position where they were dropped. Pasted content is ignored, so pasting into the
+/// text fields of the app doesn't add images.
+///
 /// For more documentation see [yew_hooks](https://docs.rs/yew-hooks/latest/yew_hooks/struct.UseDropHandle.html)
 #[function_component(UseDrop)]
 pub fn create(DropProps { children }: &DropProps) -> Html {
@@ -38,21 +50,47 @@ pub fn create(DropProps { children }: &DropProps) -> Html {
         "drop-area"
     };
 
+    // Only a drop imports content: the hook passes pasted content to the same callbacks, so a paste into a text field
+    // would be imported as well. Images that are dragged from another tab deliver a uri list and html, the text is used
+    // for everything else. Some platforms deliver a uri list for files as well, therefore the files are checked first.
+    let on_drop = move |e: DragEvent| {
+        let data_transfer = match e.data_transfer() {
+            Some(data_transfer) => data_transfer,
+            None => return,
+        };
+        let files: Vec<File> = data_transfer
+            .files()
+            .map(|list| (0..list.length()).filter_map(|i| list.item(i)).collect())
+            .unwrap_or_default();
+        let html = data_transfer.get_data("text/html").unwrap_or_default();
+        let text = match data_transfer.get_data("text/uri-list") {
+            Ok(uri_list) if !uri_list.is_empty() => uri_list,
+            _ => data_transfer.get_data("text").unwrap_or_default(),
+        };
+        let position = Position {
+            x: e.client_x() as i16,
+            y: e.client_y() as i16,
+        };
+        let dispatch = dispatch.clone();
+        spawn_local(async move {
+            let (images, errors) = if files.is_empty() {
+                import_dropped_content(&html, &text)
+            } else {
+                import_files(&files).await
+            };
+            for e in errors {
+                dispatch.apply(Msg::ShowToast(e.to_string()));
+            }
+            if !images.is_empty() {
+                dispatch.apply(Msg::AddImages(images, Some(position)));
+            }
+        });
+    };
+
     let _ = use_drop_with_options(
         node.clone(),
         UseDropOptions {
-            onfiles: Some(Box::new(move |files, _data_transfer| {
-                let mut urls = Vec::new();
-                for file in &files {
-                    let file_type = file.type_();
-                    if file_type.contains("image") {
-                        let url = Url::create_object_url_with_blob(file).unwrap();
-                        urls.push(url.clone());
-                    }
-                }
-                let call = dispatch.apply_callback(move |_: ()| Msg::AddImages(urls.clone()));
-                call.emit(());
-            })),
+            ondrop: Some(Box::new(on_drop)),
             ..Default::default()
         },
     );
@@ -0,0 +1,102 @@
+// use gloo_console::log;
+use js_sys::Uint8Array;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use yewdux::{use_dispatch, use_selector};
+
+use crate::{
+    app_state::{AppState, ImageData, Msg},
+    export::{download, export_sources, file_name, pdf_images},
+    pdf_export::to_pdf,
+    svg_export::to_svg,
+};
+
+/// Renders the buttons that export the active board. The images are embedded in the exported file, see
+/// `export_sources`; images that can't be embedded are linked by their url and the user is told so. The pdf can't link
+/// images, these are left out, see `pdf_images`.
+#[function_component(ExportButtons)]
+pub fn create() -> Html {
+    let dispatch = use_dispatch();
+    let board = use_selector(|state: &AppState| {
+        let name = state.boards.list[state.boards.active].name.clone();
+        // only the images on the artboard are exported
+        let images: Vec<ImageData> = state
+            .images
+            .iter()
+            .filter(|img| state.artboard.shows(&img.bounding_box()))
+            .cloned()
+            .collect();
+        (name, images, state.artboard.clone())
+    });
+    let page_format = use_selector(|state: &AppState| state.page_format);
+
+    let on_export_svg = {
+        let dispatch = dispatch.clone();
+        let board = board.clone();
+        Callback::from(move |_: MouseEvent| {
+            let dispatch = dispatch.clone();
+            let (name, images, artboard) = (*board).clone();
+            spawn_local(async move {
+                let sources = export_sources(&images).await;
+                let file_name = file_name(&name, "svg");
+                let result = to_svg(&images, &sources.hrefs, &artboard)
+                    .map(|svg| download(&JsValue::from_str(&svg), "image/svg+xml", &file_name));
+                let message = match result {
+                    None => Some("there are no images to export".to_string()),
+                    Some(Err(_)) => Some("the svg file can't be created".to_string()),
+                    Some(Ok(())) if sources.linked > 0 => Some(format!(
+                        "{} remote image(s) are linked by their url because they were loaded without cors",
+                        sources.linked
+                    )),
+                    Some(Ok(())) => None,
+                };
+                if let Some(message) = message {
+                    dispatch.apply(Msg::ShowToast(message));
+                }
+            });
+        })
+    };
+
+    let on_export_pdf = {
+        let dispatch = dispatch.clone();
+        let page_format = *page_format;
+        Callback::from(move |_: MouseEvent| {
+            let dispatch = dispatch.clone();
+            let (name, images, artboard) = (*board).clone();
+            spawn_local(async move {
+                let (pdf_images, skipped) = pdf_images(&images).await;
+                let file_name = file_name(&name, "pdf");
+                let result = to_pdf(&pdf_images, page_format, &artboard).map(|pdf| {
+                    download(&Uint8Array::from(&pdf[..]), "application/pdf", &file_name)
+                });
+                let message = match result {
+                    None => Some("there are no images to export".to_string()),
+                    Some(Err(_)) => Some("the pdf file can't be created".to_string()),
+                    Some(Ok(())) if skipped > 0 => Some(format!(
+                        "{} image(s) are left out because they can't be read, remote images need cors",
+                        skipped
+                    )),
+                    Some(Ok(())) => None,
+                };
+                if let Some(message) = message {
+                    dispatch.apply(Msg::ShowToast(message));
+                }
+            });
+        })
+    };
+
+    let on_next_page_format = dispatch.apply_callback(|_: MouseEvent| Msg::NextPageFormat);
+
+    // log!("render ExportButtons");
+
+    html! {
+      <>
+        <div class="header-button" onclick={on_export_svg}>{"export svg"}</div>
+        <div class="header-button" onclick={on_export_pdf}>{"export pdf"}</div>
+        <div class="header-button" onclick={on_next_page_format}>
+          {"pdf page: "}{page_format.to_string()}
+        </div>
+      </>
+    }
+}
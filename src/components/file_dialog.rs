@@ -1,34 +1,51 @@
 // use gloo_console::log;
 use wasm_bindgen::JsCast;
-use web_sys::{HtmlInputElement, Url};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
 use yew::{function_component, html, use_node_ref, Callback, Event, Html};
 use yewdux::use_dispatch;
 
-use crate::app_state::Msg;
+use crate::{
+    app_state::Msg,
+    import::{accept, import_files},
+};
 
-/// Renders a button that triggers a file dialog
+/// Renders a button that triggers a file dialog, the opened files are checked by the `import` module
 #[function_component(FileDialog)]
 pub fn create() -> Html {
     let dispatch = use_dispatch();
     let input_ref = use_node_ref();
 
-    let on_change = dispatch.apply_callback(move |e: Event| {
-        let target = e.target().unwrap();
-        let input = target.dyn_ref::<HtmlInputElement>().unwrap();
-        let mut urls = Vec::new();
-
-        if let Some(file_list) = input.files() {
-            let len = file_list.length();
-            for i in 0..len {
-                let file = file_list.item(i).unwrap();
-                let url = Url::create_object_url_with_blob(&file).unwrap();
-                urls.push(url.clone());
+    let on_change = {
+        let dispatch = dispatch.clone();
+        Callback::from(move |e: Event| {
+            let target = e.target().unwrap();
+            let input = target.dyn_ref::<HtmlInputElement>().unwrap();
+            let mut files = Vec::new();
+
+            if let Some(file_list) = input.files() {
+                let len = file_list.length();
+                for i in 0..len {
+                    if let Some(file) = file_list.item(i) {
+                        files.push(file);
+                    }
+                }
             }
-            Msg::AddImages(urls)
-        } else {
-            Msg::None
-        }
-    });
+            // allows the user to open the same file again
+            input.set_value("");
+
+            let dispatch = dispatch.clone();
+            spawn_local(async move {
+                let (images, errors) = import_files(&files).await;
+                for e in errors {
+                    dispatch.apply(Msg::ShowToast(e.to_string()));
+                }
+                if !images.is_empty() {
+                    dispatch.apply(Msg::AddImages(images, None));
+                }
+            });
+        })
+    };
 
     let on_click = {
         let i_ref = input_ref.clone();
@@ -45,7 +62,7 @@ pub fn create() -> Html {
         <input
             ref={input_ref}
             class="file_input" type="file"
-            accept="image/png, image/jpeg"
+            accept={accept()}
             multiple={true}
             onchange={on_change}
         />
@@ -0,0 +1,34 @@
+// use gloo_console::log;
+use yew::prelude::*;
+use yewdux::{use_dispatch, use_selector};
+
+use crate::{
+    app_state::{AppState, Msg},
+    guide::Guide,
+};
+
+/// Renders the guides of the active board as lines across the stage. A guide can be dragged to another position, or
+/// back onto its ruler to remove it.
+#[function_component(Guides)]
+pub fn create() -> Html {
+    let dispatch = use_dispatch();
+    let guides = use_selector(|state: &AppState| state.guides.clone());
+
+    // log!("render Guides");
+
+    html! { <>
+      {
+        guides.iter().enumerate().map(|(i, guide)| {
+            let (class, style) = match guide {
+                Guide::Horizontal(y) => ("guide guide-horizontal", format!("top:{}px;", y)),
+                Guide::Vertical(x) => ("guide guide-vertical", format!("left:{}px;", x)),
+            };
+            let on_mouse_down = dispatch.apply_callback(move |e: MouseEvent| {
+                e.prevent_default();
+                Msg::DragGuide(i)
+            });
+            html! { <div key={i} class={class} style={style} onmousedown={on_mouse_down} /> }
+        }).collect::<Html>()
+      }
+    </> }
+}
@@ -27,7 +27,12 @@ pub struct HandleProps {
 pub fn create(HandleProps { image_id, id, x, y }: &HandleProps) -> Html {
     let dispatch = use_dispatch();
     let active_handle = use_selector(|state: &AppState| state.active_handle.clone());
-    let active_image_index = use_selector(|state: &AppState| state.active_image_index);
+    let active_image_id = use_selector(|state: &AppState| {
+        state
+            .active_image_index
+            .and_then(|i| state.images.get(i))
+            .map(|img_data| img_data.id.clone())
+    });
 
     let on_pointer_down = {
         let handle_id = id.to_owned();
@@ -47,8 +52,8 @@ pub fn create(HandleProps { image_id, id, x, y }: &HandleProps) -> Html {
     }
     .to_string();
 
-    let is_active_image = if let Some(i) = active_image_index.borrow() {
-        &i.to_string() == image_id
+    let is_active_image = if let Some(i) = active_image_id.borrow() {
+        i == image_id
     } else {
         false
     };
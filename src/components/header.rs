@@ -1,9 +1,13 @@
 // use gloo_console::log;
 use yew::{function_component, html, Html};
 
-use crate::components::{file_dialog::FileDialog, logger::Logger};
+use crate::components::{
+    artboard_switch::ArtboardSwitch, board_switch::BoardSwitch, export_buttons::ExportButtons,
+    file_dialog::FileDialog, logger::Logger, placement_switch::PlacementSwitch,
+    renderer_switch::RendererSwitch, reset_button::ResetButton,
+};
 
-/// Renders the header and the mouse position logger
+/// Renders the header, the board switch, the artboard settings, the export buttons, the renderer switch, the placement switch, the reset button and the mouse position logger
 #[function_component(Header)]
 pub fn create() -> Html {
     // log!("render Header");
@@ -13,6 +17,12 @@ pub fn create() -> Html {
         <div class="header-container">
           <h3>{ "drop an image below" }</h3>
           <FileDialog />
+          <BoardSwitch />
+          <ArtboardSwitch />
+          <ExportButtons />
+          <RendererSwitch />
+          <PlacementSwitch />
+          <ResetButton />
           <Logger />
         </div>
       </header>
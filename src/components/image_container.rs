@@ -1,9 +1,17 @@
 use crate::{
-    absolute_style::AbsoluteStyle, app_state::ImageData, components::scalable_image::ScalableImage,
+    absolute_style::AbsoluteStyle,
+    app_state::{AppState, ImageData},
+    components::{
+        broken_image::BrokenImage, image_toolbar::ImageToolbar, scalable_image::ScalableImage,
+        spinner::Spinner,
+    },
+    exif::Exif,
     handle_id::HandleId,
+    image_status::ImageStatus,
 };
 // use gloo_console::log;
 use yew::prelude::*;
+use yewdux::use_selector;
 
 #[derive(Clone, Properties, PartialEq)]
 pub struct ImageContainerProps {
@@ -15,8 +23,26 @@ pub struct ImageContainerProps {
 /// When the user drags a resize handle the new dimensions of the container are calculated and stored in
 /// the store. As soon as the store is updated the container renders again and passes on the new dimensions
 /// to the ScalableImage component.
+///
+/// Hovered and selected images get an outline. The tooltip of a photo shows the camera and the capture date.
+///
+/// Once the image is loaded, a toolbar is rendered below the image that controls the pattern settings of the image. Until the
+/// image is loaded and decoded a spinner is shown, and if the image could not be loaded a placeholder is rendered instead
+/// of the image.
 #[function_component(ImageContainer)]
 pub fn create(ImageContainerProps { data }: &ImageContainerProps) -> Html {
+    let id = data.id.clone();
+    let is_hovered = use_selector(move |state: &AppState| state.hover.as_ref() == Some(&id));
+    let id = data.id.clone();
+    let is_selected = use_selector(move |state: &AppState| state.selected.contains(&id));
+    let class = if *is_selected {
+        "image-container image-container-selected"
+    } else if *is_hovered {
+        "image-container image-container-hover"
+    } else {
+        "image-container"
+    };
+
     let width = data.width;
     let height = data.height;
     let style = AbsoluteStyle {
@@ -33,13 +59,30 @@ pub fn create(ImageContainerProps { data }: &ImageContainerProps) -> Html {
     html! {
       <div
         key={data.id.clone()}
-        class="image-container" style={style}
+        class={class} style={style}
+        title={data.exif.as_ref().map(Exif::summary)}
       >
-        <ScalableImage
-          key={data.id.clone()}
-          data={data.clone()}
-        />
+        if data.status == ImageStatus::Broken {
+          <BrokenImage image_id={data.id.clone()} />
+        } else {
+          <ScalableImage
+            key={data.id.clone()}
+            data={data.clone()}
+          />
+        }
+        if data.status == ImageStatus::Loading || (data.status == ImageStatus::Loaded && !data.decoded) {
+          <Spinner />
+        }
         {HandleId::get_html(width, height, data.id.to_string())}
+        if data.status == ImageStatus::Loaded {
+          <ImageToolbar
+            image_id={data.id.clone()}
+            use_pattern={data.use_pattern}
+            resize_mode={data.resize_mode}
+            animated={!data.frame_delays.is_empty()}
+            playing={data.playing}
+          />
+        }
       </div>
     }
 }
@@ -0,0 +1,73 @@
+// use gloo_console::log;
+use yew::prelude::*;
+use yewdux::use_dispatch;
+
+use crate::{app_state::Msg, resize_mode::ResizeMode};
+
+#[derive(Clone, Properties, PartialEq)]
+pub struct ImageToolbarProps {
+    pub image_id: String,
+    pub use_pattern: bool,
+    pub resize_mode: ResizeMode,
+    pub animated: bool,
+    pub playing: bool,
+}
+
+/// Renders a small toolbar below the image that is shown when the pointer hovers over the image.
+///
+/// - the first button converts the image into a pattern or converts the pattern back into a plain image
+/// - the second button is only rendered for patterns and toggles between resizing the surface and resizing the tile
+/// - the third button is only rendered for animated images and plays or pauses the animation
+///
+/// The buttons stop the propagation of the mouse down event, otherwise clicking a button would start dragging the image.
+#[function_component(ImageToolbar)]
+pub fn create(
+    ImageToolbarProps {
+        image_id,
+        use_pattern,
+        resize_mode,
+        animated,
+        playing,
+    }: &ImageToolbarProps,
+) -> Html {
+    let dispatch = use_dispatch();
+
+    let on_toggle_pattern = {
+        let image_id = image_id.to_owned();
+        dispatch.apply_callback(move |_: MouseEvent| Msg::TogglePattern(image_id.clone()))
+    };
+
+    let on_toggle_resize_mode = {
+        let image_id = image_id.to_owned();
+        dispatch.apply_callback(move |_: MouseEvent| Msg::ToggleResizeMode(image_id.clone()))
+    };
+
+    let on_toggle_playback = {
+        let image_id = image_id.to_owned();
+        dispatch.apply_callback(move |_: MouseEvent| Msg::TogglePlayback(Some(image_id.clone())))
+    };
+
+    let on_pointer_down = Callback::from(|e: MouseEvent| {
+        e.stop_immediate_propagation();
+    });
+
+    // log!("render ImageToolbar", image_id.clone());
+
+    html! {
+      <div class="image-toolbar" onmousedown={on_pointer_down}>
+        <div class="image-toolbar-button" onclick={on_toggle_pattern}>
+          {if *use_pattern { "to image" } else { "to pattern" }}
+        </div>
+        if *use_pattern {
+          <div class="image-toolbar-button" onclick={on_toggle_resize_mode}>
+            {"resize: "}{resize_mode.to_string()}
+          </div>
+        }
+        if *animated {
+          <div class="image-toolbar-button" onclick={on_toggle_playback}>
+            {if *playing { "pause" } else { "play" }}
+          </div>
+        }
+      </div>
+    }
+}
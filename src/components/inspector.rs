@@ -0,0 +1,76 @@
+// use gloo_console::log;
+use yew::prelude::*;
+use yewdux::{use_dispatch, use_selector};
+
+use crate::{
+    app_state::{AppState, Msg},
+    components::property_input::PropertyInput,
+    image_property::ImageProperty::{self, *},
+};
+
+/// Renders the properties of the selected image as numbers that can be edited, see `PropertyInput`. The panel is only
+/// shown if a single image is selected. The tile size is only shown for patterns; the natural size is read only.
+///
+/// With the aspect lock of the image on, changing the width or the height of the image changes the other dimension as
+/// well, so that the image keeps its current ratio. The natural ratio button restores the ratio of the image file.
+#[function_component(Inspector)]
+pub fn create() -> Html {
+    let image = use_selector(|state: &AppState| match state.selected.as_slice() {
+        [id] => state.images.iter().find(|img| &img.id == id).cloned(),
+        _ => None,
+    });
+    let dispatch = use_dispatch();
+
+    // log!("render Inspector");
+
+    let img = match image.as_ref() {
+        Some(img) => img,
+        None => return html! {},
+    };
+
+    let on_toggle_lock = {
+        let image_id = img.id.clone();
+        dispatch.apply_callback(move |_: Event| Msg::ToggleAspectLock(image_id.clone()))
+    };
+
+    let on_reset_ratio = {
+        let image_id = img.id.clone();
+        dispatch.apply_callback(move |_: MouseEvent| Msg::ResetRatio(image_id.clone()))
+    };
+    let mut properties: Vec<(ImageProperty, i16)> = vec![
+        (X, img.x),
+        (Y, img.y),
+        (Width, img.width),
+        (Height, img.height),
+    ];
+    if img.use_pattern {
+        properties.push((TileWidth, img.pattern_width));
+        properties.push((TileHeight, img.pattern_height));
+    }
+    properties.push((ZIndex, img.z_index));
+
+    html! {
+      <div class="inspector">
+        {
+          properties.into_iter().map(|(property, value)| html! {
+            <PropertyInput
+              key={property.to_string()}
+              image_id={img.id.clone()}
+              {property}
+              {value}
+              keep_ratio={img.aspect_locked}
+            />
+          }).collect::<Html>()
+        }
+        <label class="inspector-row">
+          <span class="inspector-label">{"aspect lock"}</span>
+          <input type="checkbox" checked={img.aspect_locked} onchange={on_toggle_lock} />
+        </label>
+        <div class="inspector-row">
+          <span class="inspector-label">{"natural size"}</span>
+          <span>{format!("{}×{}", img.natural_width, img.natural_height)}</span>
+        </div>
+        <div class="header-button" onclick={on_reset_ratio}>{"natural ratio"}</div>
+      </div>
+    }
+}
@@ -0,0 +1,29 @@
+// use gloo_console::log;
+use yew::{function_component, html, Html};
+use yewdux::use_selector;
+
+use crate::{absolute_style::AbsoluteStyle, app_state::AppState};
+
+/// Renders the selection rectangle while the user drags over an empty part of the stage
+#[function_component(Marquee)]
+pub fn create() -> Html {
+    let marquee = use_selector(|state: &AppState| state.marquee);
+
+    // log!("render Marquee");
+
+    if let Some(bb) = *marquee {
+        let style = AbsoluteStyle {
+            x: bb.x,
+            y: bb.y,
+            width: Some(bb.width),
+            height: Some(bb.height),
+            z_index: None,
+        }
+        .to_string();
+        html! {
+          <div class="marquee" style={style} />
+        }
+    } else {
+        html! {}
+    }
+}
@@ -0,0 +1,39 @@
+// use gloo_console::log;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yewdux::{use_dispatch, use_selector};
+
+use crate::app_state::{AppState, Msg};
+
+/// Renders a button that cycles through the policies that determine the size of newly added images, see
+/// `PlacementPolicy`, and, if the policy has one, an input for the percentage of the viewport or the fixed size
+#[function_component(PlacementSwitch)]
+pub fn create() -> Html {
+    let dispatch = use_dispatch();
+    let placement_policy = use_selector(|state: &AppState| state.placement_policy);
+
+    let on_click = dispatch.apply_callback(|_: MouseEvent| Msg::NextPlacementPolicy);
+
+    // a value that isn't a number keeps the current value
+    let on_value = dispatch.apply_callback(|e: Event| {
+        let input = e.target_unchecked_into::<HtmlInputElement>();
+        match input.value().parse::<i16>() {
+            Ok(value) => Msg::SetPlacementValue(value),
+            Err(_) => Msg::None,
+        }
+    });
+
+    let (min, max) = placement_policy.value_range();
+
+    // log!("render PlacementSwitch");
+
+    html! { <>
+      <div class="header-button" onclick={on_click}>
+        {"new images: "}{placement_policy.to_string()}
+      </div>
+      if let Some(value) = placement_policy.value() {
+        <input class="header-input" type="number" min={min.to_string()} max={max.to_string()}
+          value={value.to_string()} onchange={on_value} />
+      }
+    </> }
+}
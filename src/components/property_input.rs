@@ -0,0 +1,59 @@
+// use gloo_console::log;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yewdux::use_dispatch;
+
+use crate::{app_state::Msg, image_property::ImageProperty};
+
+#[derive(Clone, Properties, PartialEq)]
+pub struct PropertyInputProps {
+    pub image_id: String,
+    pub property: ImageProperty,
+    pub value: i16,
+    pub keep_ratio: bool,
+}
+
+/// Renders a labeled number input for a property of an image, see `ImageProperty`. A valid value is sent to the
+/// reducer as soon as the user leaves the input or presses enter; invalid input is rejected: the input shows the
+/// current value again and the user is told which values are allowed.
+#[function_component(PropertyInput)]
+pub fn create(
+    PropertyInputProps {
+        image_id,
+        property,
+        value,
+        keep_ratio,
+    }: &PropertyInputProps,
+) -> Html {
+    let dispatch = use_dispatch();
+
+    let on_change = {
+        let image_id = image_id.to_owned();
+        let (property, value, keep_ratio) = (*property, *value, *keep_ratio);
+        dispatch.apply_callback(move |e: Event| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            match property.parse(&input.value()) {
+                Some(new_value) => {
+                    Msg::SetImageProperty(image_id.clone(), property, new_value, keep_ratio)
+                }
+                None => {
+                    input.set_value(&value.to_string());
+                    let (min, max) = property.range();
+                    Msg::ShowToast(format!(
+                        "the {} must be a whole number from {} to {}",
+                        property, min, max
+                    ))
+                }
+            }
+        })
+    };
+
+    // log!("render PropertyInput");
+
+    html! {
+      <label class="inspector-row">
+        <span class="inspector-label">{property.to_string()}</span>
+        <input class="inspector-input" type="number" value={value.to_string()} onchange={on_change} />
+      </label>
+    }
+}
@@ -0,0 +1,23 @@
+// use gloo_console::log;
+use yew::{function_component, html, Html, MouseEvent};
+use yewdux::{use_dispatch, use_selector};
+
+use crate::app_state::{AppState, Msg};
+
+/// Renders a button that switches between rendering the images as html elements and rendering the
+/// whole scene on a single canvas
+#[function_component(RendererSwitch)]
+pub fn create() -> Html {
+    let dispatch = use_dispatch();
+    let renderer = use_selector(|state: &AppState| state.renderer);
+
+    let on_click = dispatch.apply_callback(|_: MouseEvent| Msg::ToggleRenderer);
+
+    // log!("render RendererSwitch");
+
+    html! {
+      <div class="header-button" onclick={on_click}>
+        {"renderer: "}{renderer.to_string()}
+      </div>
+    }
+}
@@ -0,0 +1,25 @@
+// use gloo_console::log;
+use yew::{function_component, html, Html, MouseEvent};
+use yewdux::use_dispatch;
+
+use crate::app_state::Msg;
+
+/// Renders a button that removes all images from the stage after the user has confirmed
+#[function_component(ResetButton)]
+pub fn create() -> Html {
+    let dispatch = use_dispatch();
+
+    let on_click = dispatch.apply_callback(|_: MouseEvent| {
+        let window = web_sys::window().unwrap();
+        match window.confirm_with_message("Remove all images?") {
+            Ok(true) => Msg::ResetScene,
+            _ => Msg::None,
+        }
+    });
+
+    // log!("render ResetButton");
+
+    html! {
+      <div class="header-button" onclick={on_click}>{"clear"}</div>
+    }
+}
@@ -0,0 +1,53 @@
+// use gloo_console::log;
+use yew::prelude::*;
+use yewdux::{use_dispatch, use_selector};
+
+use crate::{
+    app_state::{AppState, Msg},
+    guide::{Guide, RULER_SIZE, RULER_TOP},
+};
+
+/// The distance in pixels between two labels on a ruler
+const LABEL_STEP: i16 = 100;
+
+/// Renders the rulers along the top and the left edge of the stage. The app has no zoom or pan, so a ruler shows the
+/// coordinates of the stage one to one, which are the coordinates that the logger shows and that the exports use.
+/// Pressing the mouse on a ruler drags out a new guide, see `Guide`.
+#[function_component(Rulers)]
+pub fn create() -> Html {
+    let dispatch = use_dispatch();
+    let (width, height) = *use_selector(|state: &AppState| state.viewport);
+
+    let on_horizontal = dispatch.apply_callback(|e: MouseEvent| {
+        e.prevent_default();
+        Msg::AddGuide(Guide::Horizontal(e.client_y() as i16))
+    });
+    let on_vertical = dispatch.apply_callback(|e: MouseEvent| {
+        e.prevent_default();
+        Msg::AddGuide(Guide::Vertical(e.client_x() as i16))
+    });
+
+    // log!("render Rulers");
+
+    let horizontal_labels = (1..=width / LABEL_STEP).map(|i| {
+        let style = format!("left:{}px;", i * LABEL_STEP + 2);
+        html! { <span class="ruler-label" style={style}>{i * LABEL_STEP}</span> }
+    });
+    // the labels of the vertical ruler start below the horizontal ruler
+    let vertical_labels = (1..=height / LABEL_STEP).map(|i| {
+        let style = format!("top:{}px;", i * LABEL_STEP + 2);
+        html! { <span class="ruler-label" style={style}>{i * LABEL_STEP}</span> }
+    });
+
+    html! { <>
+      <div class="ruler ruler-horizontal" style={format!("top:{}px;height:{}px;", RULER_TOP, RULER_SIZE)}
+        onmousedown={on_horizontal}>
+        { for horizontal_labels }
+      </div>
+      <div class="ruler ruler-vertical" style={format!("width:{}px;", RULER_SIZE)} onmousedown={on_vertical}>
+        { for vertical_labels }
+      </div>
+      <div class="ruler ruler-corner"
+        style={format!("top:{}px;width:{}px;height:{}px;", RULER_TOP, RULER_SIZE, RULER_SIZE)} />
+    </> }
+}
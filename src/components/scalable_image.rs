@@ -1,46 +1,51 @@
 use std::borrow::Borrow;
 
 use gloo_console::log;
-use wasm_bindgen::{JsCast, JsValue};
-use web_sys::{
-    CanvasRenderingContext2d, DomRect, HtmlCanvasElement, HtmlImageElement, SvgMatrix,
-    SvgsvgElement,
-};
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
 use yew::prelude::*;
 use yewdux::{use_dispatch, use_selector};
 
-use crate::app_state::{AppState, ImageData, Msg};
+use crate::{
+    app_state::{AppState, ImageData, Msg},
+    draw::draw_image,
+    frame_cache,
+    hit_test::{set_alpha_mask, AlphaMask},
+    image_cache,
+    orientation::Orientation,
+};
 
 #[derive(Clone, Properties, PartialEq)]
 pub struct ImageProps {
     pub data: ImageData,
 }
 
-struct Data {
-    pub width: f64,
-    pub height: f64,
-    pub natural_width: f64,
-    pub natural_height: f64,
-    pub pattern_width: f64,
-    pub pattern_height: f64,
-    pub shift_key_down: bool,
-}
-
 /// Component that renders the image
 ///
 /// This component has 2 stages, in the first stage the image is added to the document with a class 'image' that
 /// constrains the size of the image by css, and an onload handler.
 ///
-/// Once the image is loaded, the onload handler dispatches the dimensions of the image to the store where the natural width
-/// and the height of the image are stored. This triggers a rerender and then the component enters its 2nd stage where it
-/// is rendered with a fixed size and mouse handlers so that in this 2nd stage the image can be dragged around, resized and removed.
+/// Once the image is loaded, the onload handler dispatches the natural dimensions of the image to the store where the size of
+/// the image is calculated, see `PlacementPolicy`. This triggers a rerender and then the component enters its 2nd stage where it
+/// is rendered with a fixed size so that in this 2nd stage the image can be dragged around, resized and removed. Which image is
+/// clicked is determined by a hit test that ignores the transparent areas of an image, therefore the alpha channel of the image is
+/// stored as soon as the image has loaded.
 ///
-/// Because you can use an image to create a pattern as will, on the 2nd stage the image is rendered on a canvas. To be able to
-/// scale and rotate the pattern we need a SvgMatrix and to obtain an instance of such a matrix we add an otherwise unused SVG
-/// element to the document.
+/// Because you can use an image to create a pattern as will, on the 2nd stage the image is rendered on a canvas. If the boolean
+/// `use_pattern` is set the pattern will be drawn to the canvas instead of the image, see `draw_image`.
 ///
-/// If you resize the image while holding down the `shift` key the pattern size will be set stored in the state, this also flips a
-/// boolean `use_pattern` so that when you release the `shift` key the pattern will be drawn to the canvas instead of the image
+/// Redrawing a huge image on every resize is slow, therefore the image is decoded once into downscaled working copies as
+/// soon as it has loaded, see `image_cache`. Once these are available the canvas is drawn from the smallest copy that covers
+/// the size of the image on screen.
+///
+/// The backing store of the canvas is scaled by the device pixel ratio while its css size stays the size of the image, so
+/// the image is drawn sharply on high resolution displays; the canvas is redrawn when the ratio changes.
+///
+/// Animated images are decoded into frames as well, see `frame_cache`; the canvas is redrawn whenever the frame changes.
+///
+/// If the image can't be loaded, the onerror handler marks the image as broken and a placeholder is rendered instead of
+/// this component, see `BrokenImage`. Both image elements are keyed by the number of attempts, so retrying to load the
+/// image creates new elements that load the url again.
 #[function_component(ScalableImage)]
 pub fn create(ImageProps { data }: &ImageProps) -> Html {
     let ImageData {
@@ -48,34 +53,33 @@ pub fn create(ImageProps { data }: &ImageProps) -> Html {
         url,
         width,
         height,
-        natural_width,
-        natural_height,
         pattern_width,
         pattern_height,
+        pattern_x,
+        pattern_y,
+        pattern_rotation,
+        pattern_repeat,
+        pattern_mirror,
         use_pattern,
+        resize_mode,
+        decoded,
+        attempts,
+        cross_origin,
+        orientation,
+        frame,
+        is_vector,
         ..
     } = data;
 
     let dispatch = use_dispatch();
-    let svg_ref = use_node_ref();
+    let pixel_ratio = use_selector(|state: &AppState| state.pixel_ratio);
     let image_ref = use_node_ref();
     let canvas_ref = use_node_ref();
-    let shift_key_down = use_selector(|state: &AppState| state.shift_key_down);
-    let shift_key_down: bool = *shift_key_down.borrow();
-    let use_pattern = *use_pattern;
 
-    let s_ref = svg_ref.clone();
     let i_ref = image_ref.clone();
     let c_ref = canvas_ref.clone();
-    let data = Data {
-        width: *width as f64,
-        height: *height as f64,
-        natural_width: *natural_width as f64,
-        natural_height: *natural_height as f64,
-        pattern_width: *pattern_width as f64,
-        pattern_height: *pattern_height as f64,
-        shift_key_down,
-    };
+    let image_data = data.clone();
+    let ratio = *pixel_ratio;
     let create_canvas = move || {
         if let Some(canvas) = c_ref.cast::<HtmlCanvasElement>() {
             match canvas
@@ -85,60 +89,12 @@ pub fn create(ImageProps { data }: &ImageProps) -> Html {
                 .dyn_into::<CanvasRenderingContext2d>()
             {
                 Ok(ctx) => {
-                    let svg_element = s_ref.cast::<SvgsvgElement>().unwrap();
                     let img_element = i_ref.cast::<HtmlImageElement>().unwrap();
-                    ctx.set_fill_style(&JsValue::from_str("green"));
-                    ctx.fill_rect(0., 0., data.width, data.height);
-
-                    let sw = data.width / data.natural_width;
-                    let sh = data.height / data.natural_height;
-                    if data.shift_key_down || use_pattern {
-                        match ctx.draw_image_with_html_image_element_and_dw_and_dh(
-                            &img_element,
-                            0.,
-                            0.,
-                            sw,
-                            sh,
-                        ) {
-                            Ok(_) => (),
-                            Err(e) => {
-                                log!("error drawImage", e);
-                            }
-                        }
-
-                        let pattern_option = match ctx
-                            .create_pattern_with_html_image_element(&img_element, "repeat")
-                        {
-                            Ok(pattern) => pattern,
-                            Err(e) => {
-                                log!("error draw pattern", e);
-                                None
-                            }
-                        };
-                        if let Some(pattern) = pattern_option {
-                            let sw = data.pattern_width / data.natural_width;
-                            let sh = data.pattern_height / data.natural_height;
-
-                            let matrix: SvgMatrix = svg_element.create_svg_matrix();
-                            let matrix = matrix.scale_non_uniform(sw as f32, sh as f32);
-
-                            pattern.set_transform(&matrix);
-                            ctx.set_fill_style(&pattern);
-                            ctx.fill_rect(0., 0., data.width, data.height);
-                        }
-                    } else {
-                        match ctx.draw_image_with_html_image_element_and_dw_and_dh(
-                            &img_element,
-                            0.,
-                            0.,
-                            sw * data.natural_width,
-                            sh * data.natural_height,
-                        ) {
-                            Ok(_) => (),
-                            Err(e) => {
-                                log!("error draw image", e)
-                            }
-                        }
+                    let result = ctx
+                        .set_transform(ratio.0, 0., 0., ratio.0, 0., 0.)
+                        .and_then(|_| draw_image(&ctx, &img_element, &image_data, 0., 0., ratio));
+                    if let Err(e) = result {
+                        log!("error draw image", e);
                     }
                 }
                 Err(e) => {
@@ -153,68 +109,105 @@ pub fn create(ImageProps { data }: &ImageProps) -> Html {
         dispatch.apply_callback(move |e: Event| {
             let target = e.target().unwrap();
             let img = target.dyn_ref::<HtmlImageElement>().unwrap();
-            let rect: DomRect = img.get_bounding_client_rect();
-            let w = rect.width() as i16;
-            let h = rect.height() as i16;
             Msg::ImageLoaded(
                 id2.clone(),
-                w,
-                h,
-                img.natural_width() as i16,
-                img.natural_height() as i16,
+                img.natural_width().min(i16::MAX as u32) as i16,
+                img.natural_height().min(i16::MAX as u32) as i16,
             )
         })
     };
 
-    let on_pointer_down = {
-        let id = id.clone();
-        dispatch.apply_callback(move |e: MouseEvent| {
-            e.prevent_default();
-            let x = e.offset_x() as i16;
-            let y = e.offset_y() as i16;
-            Msg::SetActiveImage(id.clone(), x, y)
-        })
+    let on_error = {
+        let id = id.to_owned();
+        dispatch.apply_callback(move |_: Event| Msg::ImageFailed(id.clone()))
     };
 
-    let on_remove_image = {
+    // the mouse down event itself is handled on window level, see `Msg::MouseDown`
+    let on_pointer_down = Callback::from(|e: MouseEvent| {
+        e.prevent_default();
+    });
+
+    let on_source_load = {
         let id = id.clone();
-        dispatch.apply_callback(move |e: MouseEvent| {
-            e.stop_immediate_propagation();
-            Msg::RemoveImage(Some(id.clone()))
-        })
+        let create_canvas = create_canvas.clone();
+        let dispatch = dispatch.clone();
+        let orientation = *orientation;
+        let is_vector = *is_vector;
+        move |e: Event| {
+            let target = e.target().unwrap();
+            let img = target.dyn_ref::<HtmlImageElement>().unwrap();
+            // photos that still have to be turned upright are JPEGs, which are opaque and need no alpha mask
+            if orientation == Orientation::Normal {
+                if let Some(mask) = AlphaMask::from_image(img) {
+                    set_alpha_mask(&id, mask);
+                }
+            }
+            let id = id.clone();
+            let dispatch = dispatch.clone();
+            {
+                let id = id.clone();
+                let dispatch = dispatch.clone();
+                frame_cache::load(&id.clone(), &img.src(), move |frame_delays| {
+                    dispatch.apply(Msg::AnimationLoaded(id, frame_delays));
+                });
+            }
+            // vector images are rasterized at the size they are drawn, they have no levels
+            if is_vector {
+                dispatch.apply(Msg::ImageDecoded(id));
+            } else {
+                image_cache::decode(&id.clone(), img, orientation, move || {
+                    dispatch.apply(Msg::ImageDecoded(id));
+                });
+            }
+            create_canvas.borrow()();
+        }
     };
 
-    let data = (*width, *height, shift_key_down);
+    let data = (
+        (*width, *height, *use_pattern, *resize_mode, *decoded),
+        (*pattern_width, *pattern_height),
+        (*pattern_x, *pattern_y, *pattern_rotation),
+        (*pattern_repeat, *pattern_mirror, *frame),
+        *pixel_ratio,
+    );
     let cc = create_canvas.clone();
     use_effect_with(data, move |_| {
         cc.borrow()();
     });
 
+    // remote images are loaded with cors if possible, see `Msg::ImageFailed`
+    let cross_origin = cross_origin.then_some("anonymous");
+
     // log!("render ScalableImage");
 
     if *width == 0 && *height == 0 {
         html! {
-            <img src={url.to_string()} class="image" onload={on_load}/>
+            <img
+                key={*attempts}
+                src={url.to_string()}
+                crossorigin={cross_origin}
+                class="image"
+                onload={on_load}
+                onerror={on_error}
+            />
         }
     } else {
         html! { <>
-            <svg
-                ref={svg_ref}
-                xmlns="http://www.w3.org/2000/svg"
-                width="0"
-                height="0"
-            />
             <img
+                key={*attempts}
                 ref={image_ref}
                 class="image-hidden"
                 src={url.to_string()}
-                onload={move |_e| create_canvas.borrow()()}            />
+                crossorigin={cross_origin}
+                onload={on_source_load}
+                onerror={on_error}
+            />
             <canvas
                 ref={canvas_ref}
-                width={width.to_string()}
-                height={height.to_string()}
+                width={pixel_ratio.scale(*width).to_string()}
+                height={pixel_ratio.scale(*height).to_string()}
+                style={format!("width: {}px; height: {}px;", width, height)}
                 onmousedown={on_pointer_down}
-                ondblclick={on_remove_image}
             />
         </>
         }
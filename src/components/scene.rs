@@ -0,0 +1,434 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use gloo_console::log;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
+use yew::prelude::*;
+use yew_hooks::use_window_size;
+use yewdux::{use_dispatch, use_selector, Dispatch};
+
+use crate::{
+    absolute_style::AbsoluteStyle,
+    app_state::{AppState, ImageData, Msg},
+    bounding_box::BoundingBox,
+    components::{broken_image::BrokenImage, spinner::Spinner},
+    draw::draw_image,
+    frame_cache,
+    handle_id::{HandleId, HANDLE_SIZE},
+    hit_test::{hit_test, hit_test_handle, set_alpha_mask, AlphaMask},
+    image_cache,
+    image_status::ImageStatus,
+    orientation::Orientation,
+    pixel_ratio::PixelRatio,
+    position::Position,
+};
+
+/// Everything the scene needs to keep between renders of the component:
+///
+/// - `sources`&rarr; the image elements that are drawn onto the canvas, one for every image
+/// - `loaders`&rarr; the onload and onerror handlers of the image elements, kept alive as long as the image exists
+/// - `drawn`&rarr; the images as they will be drawn on the next animation frame
+/// - `active`&rarr; the active handle and image, the active handle is drawn in a different color
+/// - `highlight`&rarr; the hovered image and the selected images, these images are drawn with an outline
+/// - `size`&rarr; the size of the canvas in css pixels
+/// - `pixel_ratio`&rarr; the device pixel ratio the backing store of the canvas is scaled by
+/// - `dirty`&rarr; the region of the canvas that needs to be redrawn on the next animation frame
+/// - `frame_requested`&rarr; is set as long as an animation frame is pending
+#[derive(Default)]
+struct SceneCache {
+    sources: HashMap<String, HtmlImageElement>,
+    loaders: HashMap<String, [Closure<dyn FnMut()>; 2]>,
+    drawn: Vec<ImageData>,
+    active: (Option<HandleId>, Option<usize>),
+    highlight: (Option<String>, Vec<String>),
+    size: (i16, i16),
+    pixel_ratio: PixelRatio,
+    dirty: Option<BoundingBox>,
+    frame_requested: bool,
+}
+
+impl SceneCache {
+    /// adds a region to the region that needs to be redrawn
+    fn invalidate(&mut self, bb: BoundingBox) {
+        // resize handles have a border that is drawn just outside of the image
+        let bb = bb.inflate(2);
+        self.dirty = Some(match self.dirty {
+            Some(dirty) => dirty.union(&bb),
+            None => bb,
+        });
+    }
+}
+
+/// Redraws the dirty region of the canvas: the region is cleared and every image that overlaps with the
+/// region is drawn again, in order of their z-index, clipped to the region.
+fn render(cache: &Rc<RefCell<SceneCache>>, canvas_ref: &NodeRef) {
+    let mut cache = cache.borrow_mut();
+    cache.frame_requested = false;
+    let dirty = match cache.dirty.take() {
+        Some(dirty) => dirty,
+        None => return,
+    };
+    let canvas = match canvas_ref.cast::<HtmlCanvasElement>() {
+        Some(canvas) => canvas,
+        None => return,
+    };
+    let ctx = match canvas.get_context("2d") {
+        Ok(Some(ctx)) => ctx.dyn_into::<CanvasRenderingContext2d>().unwrap(),
+        _ => return,
+    };
+
+    let (x, y, w, h) = (
+        dirty.x as f64,
+        dirty.y as f64,
+        dirty.width as f64,
+        dirty.height as f64,
+    );
+    let ratio = cache.pixel_ratio;
+    if let Err(e) = ctx.set_transform(ratio.0, 0., 0., ratio.0, 0., 0.) {
+        log!("error set transform", e);
+    }
+    ctx.save();
+    ctx.begin_path();
+    ctx.rect(x, y, w, h);
+    ctx.clip();
+    ctx.clear_rect(x, y, w, h);
+
+    let (active_handle, active_image_index) = &cache.active;
+    let active_id = active_image_index
+        .and_then(|i| cache.drawn.get(i))
+        .map(|img| img.id.clone());
+    let mut images: Vec<&ImageData> = cache.drawn.iter().collect();
+    images.sort_by_key(|img| img.z_index);
+    for img in images {
+        if img.width == 0 || !img.bounding_box().inflate(2).intersects(&dirty) {
+            continue;
+        }
+        // broken images are rendered as html placeholders on top of the canvas, only their handles are drawn
+        let source = cache.sources.get(&img.id);
+        match source {
+            Some(source) if source.complete() && source.natural_width() > 0 => {
+                if let Err(e) = draw_image(&ctx, source, img, img.x as f64, img.y as f64, ratio) {
+                    log!("error draw image", e);
+                }
+            }
+            _ if img.status == ImageStatus::Broken => (),
+            _ => continue,
+        }
+        let active_handle = if active_id.as_ref() == Some(&img.id) {
+            active_handle.as_ref()
+        } else {
+            None
+        };
+        draw_handles(&ctx, img, active_handle);
+        let (hover, selected) = &cache.highlight;
+        if selected.contains(&img.id) {
+            draw_outline(&ctx, img, "#00ff00");
+        } else if hover.as_ref() == Some(&img.id) {
+            draw_outline(&ctx, img, "white");
+        }
+    }
+    ctx.restore();
+}
+
+/// Draws an outline just outside of the image, used for hovered and selected images
+fn draw_outline(ctx: &CanvasRenderingContext2d, img: &ImageData, color: &str) {
+    ctx.set_stroke_style(&JsValue::from_str(color));
+    ctx.stroke_rect(
+        img.x as f64 - 1.0,
+        img.y as f64 - 1.0,
+        img.width as f64 + 2.0,
+        img.height as f64 + 2.0,
+    );
+}
+
+/// Draws the 8 resize handles of an image, the active handle is drawn in red
+fn draw_handles(ctx: &CanvasRenderingContext2d, img: &ImageData, active_handle: Option<&HandleId>) {
+    let size = HANDLE_SIZE as f64;
+    ctx.set_stroke_style(&JsValue::from_str("white"));
+    for handle in HandleId::into_iter() {
+        let (_, x, y) = handle.get_position(img.width, img.height);
+        let color = if active_handle == Some(&handle) {
+            "#e00606"
+        } else {
+            "#00ff00"
+        };
+        let (x, y) = ((img.x + x) as f64, (img.y + y) as f64);
+        ctx.set_fill_style(&JsValue::from_str(color));
+        ctx.fill_rect(x, y, size, size);
+        ctx.stroke_rect(x, y, size, size);
+    }
+}
+
+/// Requests an animation frame that redraws the dirty region, unless a frame has already been requested. This way
+/// all changes between two frames are batched into a single redraw.
+fn request_frame(cache: &Rc<RefCell<SceneCache>>, canvas_ref: &NodeRef) {
+    if cache.borrow().frame_requested {
+        return;
+    }
+    cache.borrow_mut().frame_requested = true;
+    let c = cache.clone();
+    let canvas_ref = canvas_ref.clone();
+    let callback = Closure::once_into_js(move || render(&c, &canvas_ref));
+    let window = web_sys::window().unwrap();
+    if let Err(e) = window.request_animation_frame(callback.unchecked_ref()) {
+        log!("error request animation frame", e);
+    }
+}
+
+/// Creates the image element that is used as the source for drawing the image. Once the image element has loaded, the
+/// dimensions of the image are dispatched to the store, just like the dom renderer does, the image is drawn and the
+/// downscaled working copies of the image, or the frames of an animated image, are created. If the image element fails to load, the image is marked as broken.
+fn load_source(
+    cache: &Rc<RefCell<SceneCache>>,
+    canvas_ref: &NodeRef,
+    dispatch: &Dispatch<AppState>,
+    img: &ImageData,
+) {
+    let source = HtmlImageElement::new().unwrap();
+    let loader = {
+        let cache = cache.clone();
+        let canvas_ref = canvas_ref.clone();
+        let dispatch = dispatch.clone();
+        let source = source.clone();
+        let id = img.id.clone();
+        let orientation = img.orientation;
+        let is_vector = img.is_vector;
+        Closure::<dyn FnMut()>::new(move || {
+            // photos that still have to be turned upright are JPEGs, which are opaque and need no alpha mask
+            if orientation == Orientation::Normal {
+                if let Some(mask) = AlphaMask::from_image(&source) {
+                    set_alpha_mask(&id, mask);
+                }
+            }
+            {
+                let id = id.clone();
+                let dispatch = dispatch.clone();
+                frame_cache::load(&id.clone(), &source.src(), move |frame_delays| {
+                    dispatch.apply(Msg::AnimationLoaded(id, frame_delays));
+                });
+            }
+            // vector images are rasterized at the size they are drawn, they have no levels
+            if is_vector {
+                dispatch.apply(Msg::ImageDecoded(id.clone()));
+            } else {
+                let id = id.clone();
+                let dispatch = dispatch.clone();
+                image_cache::decode(&id.clone(), &source, orientation, move || {
+                    dispatch.apply(Msg::ImageDecoded(id));
+                });
+            }
+            let drawn = cache
+                .borrow()
+                .drawn
+                .iter()
+                .find(|d| d.id == id)
+                .map(|d| d.bounding_box());
+            match drawn {
+                Some(bb) if bb.width == 0 => {
+                    dispatch.apply(Msg::ImageLoaded(
+                        id.clone(),
+                        source.natural_width().min(i16::MAX as u32) as i16,
+                        source.natural_height().min(i16::MAX as u32) as i16,
+                    ));
+                }
+                Some(bb) => {
+                    cache.borrow_mut().invalidate(bb);
+                    request_frame(&cache, &canvas_ref);
+                }
+                None => (),
+            }
+        })
+    };
+    let on_error = {
+        let dispatch = dispatch.clone();
+        let id = img.id.clone();
+        Closure::<dyn FnMut()>::new(move || dispatch.apply(Msg::ImageFailed(id.clone())))
+    };
+    source.set_onload(Some(loader.as_ref().unchecked_ref()));
+    source.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+    // remote images are loaded with cors if possible, see `Msg::ImageFailed`
+    if img.cross_origin {
+        source.set_cross_origin(Some("anonymous"));
+    }
+    source.set_src(&img.url);
+    let mut cache = cache.borrow_mut();
+    cache.sources.insert(img.id.clone(), source);
+    cache.loaders.insert(img.id.clone(), [loader, on_error]);
+}
+
+/// Renders the whole scene on a single canvas that covers the window.
+///
+/// Instead of redrawing every image on every change, the component compares the images with the images that were
+/// drawn before and only invalidates the regions that have changed. The invalidated regions are redrawn on the next
+/// animation frame.
+///
+/// Because there are no html elements for the resize handles, the mouse down events on the canvas are hit tested in Rust
+/// to find out which handle has been clicked.
+///
+/// The spinners of images that are loading and the placeholders of broken images are rendered as html elements on top of
+/// the canvas.
+#[function_component(Scene)]
+pub fn create() -> Html {
+    let dispatch = use_dispatch();
+    let images = use_selector(|state: &AppState| state.images.clone());
+    let active =
+        use_selector(|state: &AppState| (state.active_handle.clone(), state.active_image_index));
+    let highlight = use_selector(|state: &AppState| (state.hover.clone(), state.selected.clone()));
+    let (window_width, window_height) = use_window_size();
+    let pixel_ratio = use_selector(|state: &AppState| state.pixel_ratio);
+    let canvas_ref = use_node_ref();
+    let cache = use_mut_ref(SceneCache::default);
+
+    {
+        let cache = cache.clone();
+        let canvas_ref = canvas_ref.clone();
+        let dispatch = dispatch.clone();
+        let deps = (
+            images.clone(),
+            (active.clone(), highlight.clone()),
+            (window_width, window_height),
+            *pixel_ratio,
+        );
+        use_effect_with(
+            deps,
+            move |(images, (active, highlight), (window_width, window_height), pixel_ratio)| {
+                let mut dirty: Vec<BoundingBox> = Vec::new();
+                {
+                    let mut c = cache.borrow_mut();
+                    let old_images = std::mem::replace(&mut c.drawn, images.to_vec());
+                    for img in old_images.iter() {
+                        match images.iter().find(|d| d.id == img.id) {
+                            Some(new_img) if new_img == img => (),
+                            Some(new_img) if new_img.attempts != img.attempts => {
+                                // the user retries to load a broken image, a new source is loaded below
+                                dirty.push(img.bounding_box());
+                                c.sources.remove(&img.id);
+                                c.loaders.remove(&img.id);
+                            }
+                            Some(new_img) => {
+                                dirty.push(img.bounding_box());
+                                dirty.push(new_img.bounding_box());
+                            }
+                            None => {
+                                dirty.push(img.bounding_box());
+                                c.sources.remove(&img.id);
+                                c.loaders.remove(&img.id);
+                            }
+                        }
+                    }
+                    if c.active != **active {
+                        for i in [c.active.1, active.1].into_iter().flatten() {
+                            if let Some(img) = images.get(i) {
+                                dirty.push(img.bounding_box());
+                            }
+                        }
+                        c.active = (**active).clone();
+                    }
+                    if c.highlight != **highlight {
+                        let (old_hover, old_selected) = &c.highlight;
+                        let (hover, selected) = &**highlight;
+                        let ids = old_hover
+                            .iter()
+                            .chain(old_selected)
+                            .chain(hover)
+                            .chain(selected);
+                        for id in ids {
+                            if let Some(img) = images.iter().find(|d| d.id == *id) {
+                                dirty.push(img.bounding_box());
+                            }
+                        }
+                        c.highlight = (**highlight).clone();
+                    }
+                    let size = (*window_width as i16, *window_height as i16);
+                    if c.size != size || c.pixel_ratio != *pixel_ratio {
+                        // resizing the canvas clears the canvas, so after a resize everything is redrawn
+                        c.size = size;
+                        c.pixel_ratio = *pixel_ratio;
+                        dirty.push(BoundingBox {
+                            x: 0,
+                            y: 0,
+                            width: size.0,
+                            height: size.1,
+                        });
+                    }
+                }
+                for img in images.iter() {
+                    if !cache.borrow().sources.contains_key(&img.id) {
+                        load_source(&cache, &canvas_ref, &dispatch, img);
+                    }
+                }
+                for bb in dirty {
+                    cache.borrow_mut().invalidate(bb);
+                }
+                request_frame(&cache, &canvas_ref);
+            },
+        );
+    }
+
+    // clicks on images are handled on window level, see `Msg::MouseDown`, only clicks on resize handles are handled here
+    let on_pointer_down = {
+        let cache = cache.clone();
+        Callback::from(move |e: MouseEvent| {
+            e.prevent_default();
+            let mouse = Position {
+                x: e.client_x() as i16,
+                y: e.client_y() as i16,
+            };
+            let cache = cache.borrow();
+            if let Some(i) = hit_test(&cache.drawn, mouse, false) {
+                let img = &cache.drawn[i];
+                if let Some(handle) = hit_test_handle(img, mouse) {
+                    e.stop_immediate_propagation();
+                    let (_, x, y) = handle.get_position(img.width, img.height);
+                    let anchor_x = mouse.x - img.x - x;
+                    let anchor_y = mouse.y - img.y - y;
+                    dispatch.apply(Msg::SetActiveHandle(
+                        handle,
+                        img.id.clone(),
+                        anchor_x,
+                        anchor_y,
+                    ));
+                }
+            }
+        })
+    };
+
+    let overlays = images
+        .iter()
+        .filter(|img| img.status != ImageStatus::Loaded || !img.decoded)
+        .map(|img| {
+            let style = AbsoluteStyle {
+                x: img.x,
+                y: img.y,
+                width: Some(img.width),
+                height: Some(img.height),
+                z_index: Some(img.z_index),
+            }
+            .to_string();
+            html! {
+              <div key={img.id.clone()} class="scene-overlay" style={style}>
+                if img.status == ImageStatus::Broken {
+                  <BrokenImage image_id={img.id.clone()} />
+                } else {
+                  <Spinner />
+                }
+              </div>
+            }
+        })
+        .collect::<Html>();
+
+    // log!("render Scene");
+
+    html! { <>
+      <canvas
+        ref={canvas_ref}
+        class="scene"
+        width={pixel_ratio.scale(window_width as i16).to_string()}
+        height={pixel_ratio.scale(window_height as i16).to_string()}
+        style={format!("width: {}px; height: {}px;", window_width, window_height)}
+        onmousedown={on_pointer_down}
+      />
+      {overlays}
+    </>
+    }
+}
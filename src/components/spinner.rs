@@ -0,0 +1,9 @@
+use yew::prelude::*;
+
+/// Renders a spinning circle in the top left corner of its container, shown while an image is loading or decoding
+#[function_component(Spinner)]
+pub fn create() -> Html {
+    html! {
+      <div class="spinner" />
+    }
+}
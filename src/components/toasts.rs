@@ -0,0 +1,36 @@
+// use gloo_console::log;
+use yew::prelude::*;
+use yewdux::{use_dispatch, use_selector};
+
+use crate::app_state::{AppState, Msg};
+
+/// Renders the messages for the user in the bottom right corner of the page, every message has a button to dismiss it
+#[function_component(Toasts)]
+pub fn create() -> Html {
+    let dispatch = use_dispatch();
+    let toasts = use_selector(|state: &AppState| state.toasts.clone());
+
+    // clicking a message should not start a selection on the stage
+    let on_pointer_down = Callback::from(|e: MouseEvent| {
+        e.stop_immediate_propagation();
+    });
+
+    // log!("render Toasts");
+
+    html! {
+      <div class="toasts" onmousedown={on_pointer_down}>
+        {
+          toasts.iter().map(|toast| {
+            let id = toast.id;
+            let on_dismiss = dispatch.apply_callback(move |_: MouseEvent| Msg::DismissToast(id));
+            html! {
+              <div key={id} class="toast">
+                <span>{toast.message.clone()}</span>
+                <span class="toast-dismiss" onclick={on_dismiss}>{"\u{00d7}"}</span>
+              </div>
+            }
+          }).collect::<Html>()
+        }
+      </div>
+    }
+}
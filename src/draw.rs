@@ -0,0 +1,264 @@
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    CanvasPattern, CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, ImageBitmap,
+    SvgMatrix, SvgsvgElement,
+};
+
+use crate::{
+    app_state::ImageData, frame_cache, image_cache, orientation::Orientation,
+    pixel_ratio::PixelRatio, resize_mode::ResizeMode,
+};
+
+thread_local! {
+    /// To be able to scale and rotate a pattern we need a SvgMatrix and to obtain an instance of such a matrix we
+    /// need a SVG element. The element is never added to the document.
+    static SVG_ELEMENT: SvgsvgElement = web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_element_ns(Some("http://www.w3.org/2000/svg"), "svg")
+        .unwrap()
+        .dyn_into::<SvgsvgElement>()
+        .unwrap();
+}
+
+/// The source that is drawn: the current frame of an animated image, or, once an image is decoded, the smallest level
+/// of the decoded image that covers the size on screen in device pixels. Until then the image element itself is drawn, unless the image
+/// still has to be turned upright; only the decoded levels are upright, see `image_cache`.
+///
+/// Vector images are drawn from the image element, which the browser rasterizes at the size it is drawn. Patterns of
+/// vector images are drawn from a copy that is rasterized at the size of the tile, multiplied by the device pixel ratio.
+enum Source<'a> {
+    Element(&'a HtmlImageElement),
+    Bitmap(ImageBitmap),
+    Canvas(HtmlCanvasElement),
+}
+
+impl<'a> Source<'a> {
+    fn new(
+        img_element: &'a HtmlImageElement,
+        data: &ImageData,
+        width: f64,
+        height: f64,
+    ) -> Option<Source<'a>> {
+        if data.is_vector && data.use_pattern {
+            let (width, height) = (width.round() as u32, height.round() as u32);
+            return image_cache::get_raster(&data.id, img_element, width, height)
+                .ok()
+                .map(Source::Canvas);
+        }
+        if data.is_vector {
+            return Some(Source::Element(img_element));
+        }
+        if !data.frame_delays.is_empty() {
+            if let Some(frame) = frame_cache::get_frame(&data.id, data.frame) {
+                return Some(Source::Bitmap(frame));
+            }
+        }
+        match image_cache::get_level(&data.id, width, height) {
+            Some(bitmap) => Some(Source::Bitmap(bitmap)),
+            None if data.orientation == Orientation::Normal => Some(Source::Element(img_element)),
+            None => None,
+        }
+    }
+
+    fn width(&self) -> f64 {
+        match self {
+            Source::Element(img_element) => img_element.natural_width() as f64,
+            Source::Bitmap(bitmap) => bitmap.width() as f64,
+            Source::Canvas(canvas) => canvas.width() as f64,
+        }
+    }
+
+    fn height(&self) -> f64 {
+        match self {
+            Source::Element(img_element) => img_element.natural_height() as f64,
+            Source::Bitmap(bitmap) => bitmap.height() as f64,
+            Source::Canvas(canvas) => canvas.height() as f64,
+        }
+    }
+
+    fn draw(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    ) -> Result<(), JsValue> {
+        match self {
+            Source::Element(img_element) => ctx.draw_image_with_html_image_element_and_dw_and_dh(
+                img_element,
+                x,
+                y,
+                width,
+                height,
+            ),
+            Source::Bitmap(bitmap) => {
+                ctx.draw_image_with_image_bitmap_and_dw_and_dh(bitmap, x, y, width, height)
+            }
+            Source::Canvas(canvas) => {
+                ctx.draw_image_with_html_canvas_element_and_dw_and_dh(canvas, x, y, width, height)
+            }
+        }
+    }
+
+    fn create_pattern(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        repetition: &str,
+    ) -> Result<Option<CanvasPattern>, JsValue> {
+        match self {
+            Source::Element(img_element) => {
+                ctx.create_pattern_with_html_image_element(img_element, repetition)
+            }
+            Source::Bitmap(bitmap) => ctx.create_pattern_with_image_bitmap(bitmap, repetition),
+            Source::Canvas(canvas) => {
+                ctx.create_pattern_with_html_canvas_element(canvas, repetition)
+            }
+        }
+    }
+}
+
+/// Draws an image, or the pattern of an image if `use_pattern` is set, onto a canvas with its top left corner at
+/// position `x`, `y`. Used by both the canvas of a single image and the canvas that renders the whole scene.
+///
+/// The position and size are in css pixels, the context is expected to be scaled by the `pixel_ratio` already; the
+/// ratio is only used to pick a source that is sharp at the resolution of the canvas.
+///
+/// While the tile of a pattern is being resized, the outline of the tile is drawn on top of the pattern.
+pub fn draw_image(
+    ctx: &CanvasRenderingContext2d,
+    img_element: &HtmlImageElement,
+    data: &ImageData,
+    x: f64,
+    y: f64,
+    pixel_ratio: PixelRatio,
+) -> Result<(), JsValue> {
+    ctx.save();
+    ctx.translate(x, y)?;
+    ctx.set_fill_style(&JsValue::from_str("green"));
+    ctx.fill_rect(0., 0., data.width as f64, data.height as f64);
+    let result = draw_content(ctx, img_element, data, pixel_ratio);
+    ctx.restore();
+    result
+}
+
+/// Draws an image, or its pattern, with its top left corner at the origin of the context, without the background that
+/// `draw_image` fills first; used by the pdf export, which keeps the transparency of the images.
+pub fn draw_content(
+    ctx: &CanvasRenderingContext2d,
+    img_element: &HtmlImageElement,
+    data: &ImageData,
+    pixel_ratio: PixelRatio,
+) -> Result<(), JsValue> {
+    let width = data.width as f64;
+    let height = data.height as f64;
+
+    if data.use_pattern {
+        let source = Source::new(
+            img_element,
+            data,
+            data.pattern_width as f64 * pixel_ratio.0,
+            data.pattern_height as f64 * pixel_ratio.0,
+        );
+        draw_pattern(ctx, source.as_ref(), data)
+    } else {
+        let (device_width, device_height) = (width * pixel_ratio.0, height * pixel_ratio.0);
+        match Source::new(img_element, data, device_width, device_height) {
+            Some(source) => source.draw(ctx, 0., 0., width, height),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The pattern transform first translates the pattern to its origin, then rotates it and finally scales the tile to
+/// the pattern size.
+fn draw_pattern(
+    ctx: &CanvasRenderingContext2d,
+    source: Option<&Source>,
+    data: &ImageData,
+) -> Result<(), JsValue> {
+    let pattern = match source {
+        Some(source) => create_pattern(ctx, source, data)?.map(|pattern| (pattern, source)),
+        None => None,
+    };
+    if let Some((pattern, source)) = pattern {
+        let sw = (data.pattern_width as f64 / source.width()) as f32;
+        let sh = (data.pattern_height as f64 / source.height()) as f32;
+
+        let matrix: SvgMatrix = SVG_ELEMENT.with(|svg_element| svg_element.create_svg_matrix());
+        let matrix = matrix
+            .translate(data.pattern_x as f32, data.pattern_y as f32)
+            .rotate(data.pattern_rotation as f32)
+            .scale_non_uniform(sw, sh);
+
+        pattern.set_transform(&matrix);
+        ctx.set_fill_style(&pattern);
+        ctx.fill_rect(0., 0., data.width as f64, data.height as f64);
+    }
+    if data.resize_mode == ResizeMode::Tile {
+        draw_tile_outline(ctx, data)?;
+    }
+    Ok(())
+}
+
+/// Creates the pattern from the image. If the pattern is mirrored, the image is first drawn 4 times onto
+/// an offscreen canvas: as is, flipped horizontally, flipped vertically and flipped in both directions. This
+/// canvas, which is twice as wide and twice as high as the image, is then used as the source of the pattern.
+fn create_pattern(
+    ctx: &CanvasRenderingContext2d,
+    source: &Source,
+    data: &ImageData,
+) -> Result<Option<CanvasPattern>, JsValue> {
+    let repetition = data.pattern_repeat.to_string();
+    if !data.pattern_mirror {
+        return source.create_pattern(ctx, &repetition);
+    }
+
+    let source_width = source.width();
+    let source_height = source.height();
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(2 * source_width as u32);
+    canvas.set_height(2 * source_height as u32);
+    let tile_ctx = canvas
+        .get_context("2d")?
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()?;
+
+    for (flip_x, flip_y) in [(false, false), (true, false), (false, true), (true, true)] {
+        tile_ctx.save();
+        tile_ctx.translate(
+            if flip_x { 2.0 * source_width } else { 0.0 },
+            if flip_y { 2.0 * source_height } else { 0.0 },
+        )?;
+        tile_ctx.scale(
+            if flip_x { -1.0 } else { 1.0 },
+            if flip_y { -1.0 } else { 1.0 },
+        )?;
+        source.draw(&tile_ctx, 0., 0., source_width, source_height)?;
+        tile_ctx.restore();
+    }
+
+    ctx.create_pattern_with_html_canvas_element(&canvas, &repetition)
+}
+
+/// Draws a dashed outline around the tile at the origin of the pattern, using the same transform as the pattern.
+fn draw_tile_outline(ctx: &CanvasRenderingContext2d, data: &ImageData) -> Result<(), JsValue> {
+    ctx.save();
+    ctx.translate(data.pattern_x as f64, data.pattern_y as f64)?;
+    ctx.rotate((data.pattern_rotation as f64).to_radians())?;
+    ctx.set_line_dash(&js_sys::Array::of2(&4.0.into(), &4.0.into()))?;
+    ctx.set_stroke_style(&JsValue::from_str("#00ff00"));
+    ctx.stroke_rect(
+        0.,
+        0.,
+        data.pattern_width as f64,
+        data.pattern_height as f64,
+    );
+    ctx.restore();
+    Ok(())
+}
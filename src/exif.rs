@@ -0,0 +1,374 @@
+use serde::{Deserialize, Serialize};
+
+use crate::orientation::Orientation;
+
+/// EXIF tags in the first image file directory
+const TAG_MAKE: u16 = 0x010f;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+/// EXIF tags in the EXIF sub directory
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_COLOR_SPACE: u16 = 0xa001;
+
+/// The metadata of a JPEG photo that is read from its EXIF data:
+///
+/// - `orientation`&rarr; How the photo has to be transformed to show it upright
+/// - `date_time`&rarr; The date and time the photo was taken, formatted as `YYYY:MM:DD HH:MM:SS`
+/// - `make`, `model`&rarr; The manufacturer and the model of the camera
+/// - `color_space`&rarr; The colour space that is stored in the EXIF data, for instance `sRGB`
+/// - `icc_profile`&rarr; Is set if the JPEG embeds an ICC colour profile, which the browser applies when it draws the photo
+#[derive(Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exif {
+    pub orientation: Orientation,
+    pub date_time: Option<String>,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub color_space: Option<String>,
+    pub icc_profile: bool,
+}
+
+impl Exif {
+    /// Returns a short description of the camera and the capture date, used as a tooltip
+    pub fn summary(&self) -> String {
+        let camera = match (&self.make, &self.model) {
+            // the model often starts with the name of the manufacturer
+            (Some(make), Some(model)) if model.starts_with(make.as_str()) => model.clone(),
+            (Some(make), Some(model)) => make.to_string() + " " + model,
+            (Some(camera), None) | (None, Some(camera)) => camera.clone(),
+            (None, None) => String::new(),
+        };
+        [
+            Some(camera),
+            self.date_time.clone(),
+            self.color_space.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<String>>()
+        .join(", ")
+    }
+}
+
+/// Reads the values from the TIFF structure inside the EXIF segment, in the byte order of the structure
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    /// Reads an ASCII value, values of up to 4 bytes are stored in the entry itself
+    fn ascii(&self, entry: usize) -> Option<String> {
+        let count = self.u32(entry + 4)? as usize;
+        let offset = if count <= 4 {
+            entry + 8
+        } else {
+            self.u32(entry + 8)? as usize
+        };
+        let bytes = self.data.get(offset..offset.checked_add(count)?)?;
+        let value = String::from_utf8_lossy(bytes);
+        let value = value.trim_end_matches('\0').trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+
+    /// Returns the offsets of the 12 byte entries of the image file directory at `offset`
+    fn entries(&self, offset: usize) -> impl Iterator<Item = usize> + '_ {
+        let count = self.u16(offset).unwrap_or(0) as usize;
+        (0..count)
+            .map(move |i| offset + 2 + i * 12)
+            .take_while(|entry| entry + 12 <= self.data.len())
+    }
+}
+
+/// Parses the TIFF structure of an EXIF segment
+fn parse_tiff(data: &[u8], exif: &mut Exif) -> Option<()> {
+    let little_endian = match data.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let tiff = Tiff {
+        data,
+        little_endian,
+    };
+    if tiff.u16(2)? != 42 {
+        return None;
+    }
+
+    let mut exif_ifd = None;
+    for entry in tiff.entries(tiff.u32(4)? as usize) {
+        match tiff.u16(entry)? {
+            TAG_ORIENTATION => exif.orientation = Orientation::from_tag(tiff.u16(entry + 8)?),
+            TAG_MAKE => exif.make = tiff.ascii(entry),
+            TAG_MODEL => exif.model = tiff.ascii(entry),
+            TAG_DATE_TIME => exif.date_time = tiff.ascii(entry),
+            TAG_EXIF_IFD => exif_ifd = tiff.u32(entry + 8),
+            _ => (),
+        }
+    }
+
+    if let Some(offset) = exif_ifd {
+        for entry in tiff.entries(offset as usize) {
+            match tiff.u16(entry)? {
+                // the moment the photo was taken is preferred over the moment the file was last changed
+                TAG_DATE_TIME_ORIGINAL => {
+                    exif.date_time = tiff.ascii(entry).or(exif.date_time.take())
+                }
+                TAG_COLOR_SPACE => {
+                    exif.color_space = match tiff.u16(entry + 8)? {
+                        1 => Some("sRGB".to_string()),
+                        2 => Some("Adobe RGB".to_string()),
+                        0xffff => Some("uncalibrated".to_string()),
+                        _ => None,
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    Some(())
+}
+
+/// Parses the EXIF data of a JPEG file. Only the start of the file is needed, the segments with metadata come before
+/// the image data; if a segment runs past the end of the data, the EXIF data that has been found so far is returned.
+/// Returns `None` if the data is not a JPEG file or if the file has no EXIF data.
+pub fn parse_jpeg(bytes: &[u8]) -> Option<Exif> {
+    if bytes.get(0..2)? != [0xff, 0xd8] {
+        return None;
+    }
+    let mut exif: Option<Exif> = None;
+    let mut icc_profile = false;
+    let mut offset = 2;
+    while let Some([0xff, marker, high, low, ..]) = bytes.get(offset..) {
+        // the start of the image data or the end of the image, no metadata follows
+        if *marker == 0xda || *marker == 0xd9 {
+            break;
+        }
+        let length = u16::from_be_bytes([*high, *low]) as usize;
+        let segment = match bytes.get(offset + 4..offset + 2 + length) {
+            Some(segment) => segment,
+            // a large segment, like an ICC profile, may be cut off when only the start of the file is read
+            None => {
+                let rest = &bytes[offset + 4..];
+                icc_profile |= *marker == 0xe2 && rest.starts_with(b"ICC_PROFILE\0");
+                break;
+            }
+        };
+        match marker {
+            0xe1 if segment.starts_with(b"Exif\0\0") => {
+                let mut data = Exif::default();
+                if parse_tiff(&segment[6..], &mut data).is_some() {
+                    exif = Some(data);
+                }
+            }
+            0xe2 if segment.starts_with(b"ICC_PROFILE\0") => icc_profile = true,
+            _ => (),
+        }
+        offset += 2 + length;
+    }
+    exif.map(|exif| Exif {
+        icc_profile,
+        ..exif
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the values of a TIFF structure in the given byte order
+    struct Writer {
+        data: Vec<u8>,
+        little_endian: bool,
+    }
+
+    impl Writer {
+        fn u16(&mut self, value: u16) {
+            let bytes = if self.little_endian {
+                value.to_le_bytes()
+            } else {
+                value.to_be_bytes()
+            };
+            self.data.extend_from_slice(&bytes);
+        }
+
+        fn u32(&mut self, value: u32) {
+            let bytes = if self.little_endian {
+                value.to_le_bytes()
+            } else {
+                value.to_be_bytes()
+            };
+            self.data.extend_from_slice(&bytes);
+        }
+
+        /// writes a directory entry whose value fits in the entry
+        fn short_entry(&mut self, tag: u16, value: u16) {
+            self.u16(tag);
+            self.u16(3);
+            self.u32(1);
+            self.u16(value);
+            self.u16(0);
+        }
+
+        /// writes a directory entry whose value is stored at `offset`
+        fn offset_entry(&mut self, tag: u16, kind: u16, count: u32, offset: u32) {
+            self.u16(tag);
+            self.u16(kind);
+            self.u32(count);
+            self.u32(offset);
+        }
+    }
+
+    const MAKE: &[u8] = b"Canon\0";
+    const DATE_TIME: &[u8] = b"2024:05:01 12:30:00\0";
+
+    /// Returns a TIFF structure with an orientation, a camera make, the capture date and the colour space
+    fn tiff(little_endian: bool, orientation: u16) -> Vec<u8> {
+        let mut w = Writer {
+            data: Vec::new(),
+            little_endian,
+        };
+        w.data
+            .extend_from_slice(if little_endian { b"II" } else { b"MM" });
+        w.u16(42);
+        w.u32(8);
+        // the first directory holds 3 entries and is followed by the make, then the exif directory with 2 entries
+        let make_offset = 8 + 2 + 3 * 12 + 4;
+        let exif_offset = make_offset + MAKE.len() as u32;
+        let date_time_offset = exif_offset + 2 + 2 * 12 + 4;
+        w.u16(3);
+        w.short_entry(TAG_ORIENTATION, orientation);
+        w.offset_entry(TAG_MAKE, 2, MAKE.len() as u32, make_offset);
+        w.offset_entry(TAG_EXIF_IFD, 4, 1, exif_offset);
+        w.u32(0);
+        w.data.extend_from_slice(MAKE);
+        w.u16(2);
+        w.offset_entry(
+            TAG_DATE_TIME_ORIGINAL,
+            2,
+            DATE_TIME.len() as u32,
+            date_time_offset,
+        );
+        w.short_entry(TAG_COLOR_SPACE, 1);
+        w.u32(0);
+        w.data.extend_from_slice(DATE_TIME);
+        w.data
+    }
+
+    /// Returns a segment of a JPEG file
+    fn segment(marker: u8, content: &[u8]) -> Vec<u8> {
+        let length = (content.len() + 2) as u16;
+        [&[0xff, marker], &length.to_be_bytes()[..], content].concat()
+    }
+
+    /// Returns the start of a JPEG file with an EXIF segment, followed by an ICC profile of `icc_size` bytes if that
+    /// isn't 0
+    fn jpeg(tiff: &[u8], icc_size: usize) -> Vec<u8> {
+        let mut bytes = vec![0xff, 0xd8];
+        bytes.extend(segment(0xe0, b"JFIF\0\x01\x01"));
+        bytes.extend(segment(0xe1, &[b"Exif\0\0", tiff].concat()));
+        if icc_size > 0 {
+            let mut profile = b"ICC_PROFILE\0".to_vec();
+            profile.resize(icc_size, 0);
+            bytes.extend(segment(0xe2, &profile));
+        }
+        bytes.extend(segment(0xda, &[0; 10]));
+        bytes
+    }
+
+    #[test]
+    fn reads_both_byte_orders() {
+        for little_endian in [true, false] {
+            let mut exif = Exif::default();
+            assert!(parse_tiff(&tiff(little_endian, 6), &mut exif).is_some());
+            assert!(exif.orientation == Orientation::Rotate90);
+            assert_eq!(exif.make.as_deref(), Some("Canon"));
+            assert_eq!(exif.date_time.as_deref(), Some("2024:05:01 12:30:00"));
+            assert_eq!(exif.color_space.as_deref(), Some("sRGB"));
+        }
+    }
+
+    #[test]
+    fn reads_every_orientation() {
+        let expected = [
+            Orientation::Normal,
+            Orientation::FlipHorizontal,
+            Orientation::Rotate180,
+            Orientation::FlipVertical,
+            Orientation::Transpose,
+            Orientation::Rotate90,
+            Orientation::Transverse,
+            Orientation::Rotate270,
+        ];
+        for (tag, orientation) in (1..).zip(expected) {
+            let exif = parse_jpeg(&jpeg(&tiff(tag % 2 == 0, tag), 0)).unwrap();
+            assert!(exif.orientation == orientation, "tag {}", tag);
+        }
+        // unknown values leave the photo as it is
+        let exif = parse_jpeg(&jpeg(&tiff(true, 9), 0)).unwrap();
+        assert!(exif.orientation == Orientation::Normal);
+    }
+
+    #[test]
+    fn rejects_data_that_is_not_exif() {
+        let mut exif = Exif::default();
+        assert!(parse_tiff(b"XX\0\x2a\0\0\0\x08", &mut exif).is_none());
+        assert!(parse_tiff(b"II\x2b\0\x08\0\0\0", &mut exif).is_none());
+        assert!(parse_jpeg(b"\x89PNG\r\n\x1a\n").is_none());
+        assert!(parse_jpeg(&[0xff, 0xd8, 0xff, 0xda, 0, 2]).is_none());
+        assert!(parse_jpeg(&[]).is_none());
+    }
+
+    #[test]
+    fn detects_an_icc_profile() {
+        let exif = parse_jpeg(&jpeg(&tiff(true, 1), 100)).unwrap();
+        assert!(exif.icc_profile);
+        let exif = parse_jpeg(&jpeg(&tiff(true, 1), 0)).unwrap();
+        assert!(!exif.icc_profile);
+    }
+
+    #[test]
+    fn keeps_the_exif_data_when_a_later_segment_is_cut_off() {
+        // only the start of a file is read, a large ICC profile can run past the end of the data
+        let bytes = jpeg(&tiff(false, 8), 60_000);
+        let exif = parse_jpeg(&bytes[..1000]).unwrap();
+        assert!(exif.orientation == Orientation::Rotate270);
+        assert!(exif.icc_profile);
+    }
+
+    #[test]
+    fn truncated_files_do_not_panic() {
+        let bytes = jpeg(&tiff(true, 3), 200);
+        let tiff_start = bytes.windows(6).position(|w| w == b"Exif\0\0").unwrap() + 6;
+        for length in 0..bytes.len() {
+            let exif = parse_jpeg(&bytes[..length]);
+            // the EXIF segment is only used if it is complete
+            assert_eq!(exif.is_some(), length >= tiff_start + tiff(true, 3).len());
+        }
+        let tiff = tiff(false, 3);
+        for length in 0..tiff.len() {
+            let mut exif = Exif::default();
+            let _ = parse_tiff(&tiff[..length], &mut exif);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use gloo_console::log;
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, BlobPropertyBag, CanvasRenderingContext2d, HtmlAnchorElement, HtmlCanvasElement,
+    HtmlImageElement, Response, Url,
+};
+
+use crate::{
+    app_state::ImageData,
+    draw::draw_content,
+    exif::parse_jpeg,
+    frame_cache,
+    image_status::ImageStatus,
+    orientation::Orientation,
+    pdf_export::{jpeg_info, PdfImage, PdfImageData},
+    pixel_ratio::PixelRatio,
+    resize_mode::ResizeMode,
+};
+
+/// The number of milliseconds after which the object url of a download is revoked
+const REVOKE_DELAY: i32 = 10_000;
+/// The number of pixels per css pixel at which images are rasterized for the pdf export
+const PDF_SCALE: f64 = 2.;
+/// The maximum number of pixels of a rasterized image, large images are rasterized at a lower scale
+const PDF_MAX_PIXELS: f64 = 4096. * 4096.;
+
+/// The sources of the images that are exported
+///
+/// - `hrefs`&rarr; The source of every image by image id, a data uri or, for images that can't be read, the url
+/// - `linked`&rarr; The number of images that are linked by their url because their pixels can't be read, these are
+/// remote images that have been loaded without cors
+#[derive(Default)]
+pub struct ExportSources {
+    pub hrefs: HashMap<String, String>,
+    pub linked: usize,
+}
+
+/// Draws the frame of an animated image that is shown onto a canvas and returns it as png data uri, so that the frame
+/// that has been picked is exported instead of the whole animation
+fn frame_data_uri(img: &ImageData) -> Result<Option<String>, JsValue> {
+    let frame = match frame_cache::get_frame(&img.id, img.frame) {
+        Some(frame) => frame,
+        None => return Ok(None),
+    };
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(frame.width());
+    canvas.set_height(frame.height());
+    let ctx = canvas
+        .get_context("2d")?
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    ctx.draw_image_with_image_bitmap(&frame, 0., 0.)?;
+    canvas.to_data_url().map(Some)
+}
+
+/// Reads the file an image is loaded from and returns its contents and its mime type
+async fn file_bytes(url: &str) -> Result<(Vec<u8>, String), JsValue> {
+    let window = web_sys::window().unwrap();
+    let response = JsFuture::from(window.fetch_with_str(url))
+        .await?
+        .dyn_into::<Response>()?;
+    let blob = JsFuture::from(response.blob()?).await?.dyn_into::<Blob>()?;
+    let buffer = JsFuture::from(blob.array_buffer()).await?;
+    Ok((Uint8Array::new(&buffer).to_vec(), blob.type_()))
+}
+
+/// Reads the file an image is loaded from and returns it as data uri
+async fn file_data_uri(url: &str) -> Result<String, JsValue> {
+    let (bytes, mime_type) = file_bytes(url).await?;
+    let mime_type = match mime_type {
+        mime_type if mime_type.is_empty() => "application/octet-stream".to_string(),
+        mime_type => mime_type,
+    };
+    Ok(format!(
+        "data:{};base64,{}",
+        mime_type,
+        base64::encode(bytes)
+    ))
+}
+
+/// Collects the sources of the images that have loaded. Images are embedded as data uris, animated images with the
+/// frame that is shown. Remote images that have been loaded without cors can't be read, they are linked by their url.
+pub async fn export_sources(images: &[ImageData]) -> ExportSources {
+    let mut sources = ExportSources::default();
+    for img in images
+        .iter()
+        .filter(|img| img.status == ImageStatus::Loaded)
+    {
+        let href = if img.tainted {
+            sources.linked += 1;
+            Ok(Some(img.url.clone()))
+        } else if !img.frame_delays.is_empty() {
+            frame_data_uri(img)
+        } else if img.url.starts_with("data:") {
+            Ok(Some(img.url.clone()))
+        } else {
+            file_data_uri(&img.url).await.map(Some)
+        };
+        match href {
+            Ok(Some(href)) => {
+                sources.hrefs.insert(img.id.clone(), href);
+            }
+            Ok(None) => (),
+            Err(e) => log!("error export image", e),
+        }
+    }
+    sources
+}
+
+/// Returns the JPEG file of an image if it can be embedded in a pdf as is: a photo that is drawn as a whole, upright
+/// as stored and in colours that pdf readers show without conversion. Other images are rasterized. Browsers apply the
+/// EXIF orientation themselves, so the orientation and the colour profile are read from the file that is embedded.
+async fn embeddable_jpeg(img: &ImageData) -> Option<PdfImage> {
+    if img.use_pattern || !img.frame_delays.is_empty() || img.is_vector {
+        return None;
+    }
+    let (bytes, _) = file_bytes(&img.url).await.ok()?;
+    if let Some(exif) = parse_jpeg(&bytes) {
+        if exif.orientation != Orientation::Normal || exif.icc_profile {
+            return None;
+        }
+    }
+    match jpeg_info(&bytes)? {
+        (pixel_width, pixel_height, components) if components == 1 || components == 3 => {
+            Some(PdfImage {
+                bounding_box: img.bounding_box(),
+                pixel_width,
+                pixel_height,
+                data: PdfImageData::Jpeg(bytes, components),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Draws an image the way it is shown, without the background, onto an offscreen canvas at `PDF_SCALE` times its
+/// size and reads back the pixels. The image is loaded into a new element, which is only used by the draw function for
+/// images that aren't decoded, like vector images.
+async fn rasterize(img: &ImageData) -> Result<PdfImage, JsValue> {
+    let img_element = HtmlImageElement::new()?;
+    if img.cross_origin {
+        img_element.set_cross_origin(Some("anonymous"));
+    }
+    img_element.set_src(&img.url);
+    JsFuture::from(img_element.decode()).await?;
+
+    let (width, height) = (img.width as f64, img.height as f64);
+    let scale = PDF_SCALE.min((PDF_MAX_PIXELS / (width * height)).sqrt());
+    let pixel_width = ((width * scale).round() as u32).max(1);
+    let pixel_height = ((height * scale).round() as u32).max(1);
+
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(pixel_width);
+    canvas.set_height(pixel_height);
+    let ctx = canvas
+        .get_context("2d")?
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    ctx.scale(pixel_width as f64 / width, pixel_height as f64 / height)?;
+    // the outline of the tile is only shown while the tile is being resized
+    let mut data = img.clone();
+    data.resize_mode = ResizeMode::Surface;
+    draw_content(&ctx, &img_element, &data, PixelRatio(scale))?;
+    let pixels = ctx
+        .get_image_data(0., 0., pixel_width as f64, pixel_height as f64)?
+        .data()
+        .to_vec();
+
+    Ok(PdfImage {
+        bounding_box: img.bounding_box(),
+        pixel_width,
+        pixel_height,
+        data: PdfImageData::Rgba(pixels),
+    })
+}
+
+/// Collects the images of a pdf export in the order of their z-index, see `pdf_export`. JPEG photos are embedded as
+/// is, all other images are rasterized. Remote images that have been loaded without cors can't be read and are left
+/// out, these are counted in `skipped`.
+pub async fn pdf_images(images: &[ImageData]) -> (Vec<PdfImage>, usize) {
+    let mut exported: Vec<&ImageData> = images
+        .iter()
+        .filter(|img| img.status == ImageStatus::Loaded && img.width > 0 && img.height > 0)
+        .collect();
+    exported.sort_by_key(|img| img.z_index);
+
+    let mut pdf_images = Vec::new();
+    let mut skipped = 0;
+    for img in exported {
+        if img.tainted {
+            skipped += 1;
+            continue;
+        }
+        let pdf_image = match embeddable_jpeg(img).await {
+            Some(pdf_image) => Ok(pdf_image),
+            None => rasterize(img).await,
+        };
+        match pdf_image {
+            Ok(pdf_image) => pdf_images.push(pdf_image),
+            Err(e) => {
+                log!("error export image", e);
+                skipped += 1;
+            }
+        }
+    }
+    (pdf_images, skipped)
+}
+
+/// Offers the contents to the user as a file download
+pub fn download(contents: &JsValue, mime_type: &str, file_name: &str) -> Result<(), JsValue> {
+    let mut options = BlobPropertyBag::new();
+    options.type_(mime_type);
+    let blob = Blob::new_with_blob_sequence_and_options(&Array::of1(contents), &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+    let document = web_sys::window().unwrap().document().unwrap();
+    let anchor = document
+        .create_element("a")?
+        .dyn_into::<HtmlAnchorElement>()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    // the download starts asynchronously, the url is revoked once the browser has read the file
+    let revoke = Closure::once_into_js(move || {
+        let _ = Url::revoke_object_url(&url);
+    });
+    web_sys::window()
+        .unwrap()
+        .set_timeout_with_callback_and_timeout_and_arguments_0(
+            revoke.unchecked_ref(),
+            REVOKE_DELAY,
+        )?;
+    Ok(())
+}
+
+/// Returns a file name for the export of a board, characters that aren't allowed in file names are replaced
+pub fn file_name(board_name: &str, extension: &str) -> String {
+    let name: String = board_name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect();
+    format!("{}.{}", name.trim(), extension)
+}
@@ -0,0 +1,109 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
+use gloo_console::log;
+use js_sys::Uint8Array;
+use wasm_bindgen::{Clamped, JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{Blob, ImageBitmap, Response};
+
+use crate::animation::{decode_animation, is_animation_format};
+
+/// The number of bytes that are read to recognize the format of an image
+const SIGNATURE_SIZE: i32 = 16;
+
+thread_local! {
+    /// The frames of the animated images stored by image id
+    static FRAMES: RefCell<HashMap<String, Vec<ImageBitmap>>> = RefCell::new(HashMap::new());
+    /// The ids of the images whose frames are being decoded
+    static PENDING: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// Reads the image from its url. Only images in a format that can be animated are read completely.
+async fn read_image(url: &str) -> Result<Option<Vec<u8>>, JsValue> {
+    let window = web_sys::window().unwrap();
+    let response = JsFuture::from(window.fetch_with_str(url))
+        .await?
+        .dyn_into::<Response>()?;
+    let blob = JsFuture::from(response.blob()?).await?.dyn_into::<Blob>()?;
+    let signature = blob.slice_with_i32_and_i32(0, SIGNATURE_SIZE)?;
+    let signature = JsFuture::from(signature.array_buffer()).await?;
+    if !is_animation_format(&Uint8Array::new(&signature).to_vec()) {
+        return Ok(None);
+    }
+    let buffer = JsFuture::from(blob.array_buffer()).await?;
+    Ok(Some(Uint8Array::new(&buffer).to_vec()))
+}
+
+/// Reads and decodes the image and creates an ImageBitmap for every frame. Returns the frames and the delay of every
+/// frame, or `None` if the image is not animated.
+async fn create_frames(url: &str) -> Result<Option<(Vec<ImageBitmap>, Vec<u32>)>, JsValue> {
+    let bytes = match read_image(url).await? {
+        Some(bytes) => bytes,
+        None => return Ok(None),
+    };
+    let animation = match decode_animation(&bytes) {
+        Some(animation) => animation,
+        None => return Ok(None),
+    };
+
+    let window = web_sys::window().unwrap();
+    let mut frames = Vec::new();
+    let mut delays = Vec::new();
+    for frame in animation.frames {
+        let image_data = web_sys::ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&frame.rgba),
+            animation.width,
+            animation.height,
+        )?;
+        let promise = window.create_image_bitmap_with_image_data(&image_data)?;
+        frames.push(JsFuture::from(promise).await?.dyn_into::<ImageBitmap>()?);
+        delays.push(frame.delay);
+    }
+    Ok(Some((frames, delays)))
+}
+
+/// Decodes the frames of an animated GIF, WebP or PNG image. The decoding happens asynchronously, `on_loaded` is called
+/// with the delay of every frame as soon as all frames are available. Images that are not animated are ignored, just
+/// like images that can't be read, for instance remote images that are loaded without cors.
+pub fn load(id: &str, url: &str, on_loaded: impl FnOnce(Vec<u32>) + 'static) {
+    let is_known = FRAMES.with(|frames| frames.borrow().contains_key(id))
+        || !PENDING.with(|pending| pending.borrow_mut().insert(id.to_string()));
+    if is_known {
+        return;
+    }
+
+    let id = id.to_string();
+    let url = url.to_string();
+    spawn_local(async move {
+        let result = create_frames(&url).await;
+        // the image may have been removed while it was being decoded
+        let is_removed = !PENDING.with(|pending| pending.borrow_mut().remove(&id));
+        match result {
+            Ok(Some((new_frames, _))) if is_removed => {
+                new_frames.iter().for_each(|frame| frame.close())
+            }
+            Ok(Some((new_frames, delays))) => {
+                FRAMES.with(|frames| frames.borrow_mut().insert(id, new_frames));
+                on_loaded(delays);
+            }
+            Ok(None) => (),
+            Err(e) => log!("error decode animation", e),
+        }
+    });
+}
+
+/// Returns the frame at `index` of an animated image
+pub fn get_frame(id: &str, index: usize) -> Option<ImageBitmap> {
+    FRAMES.with(|frames| frames.borrow().get(id)?.get(index).cloned())
+}
+
+/// Releases the frames of an animated image
+pub fn remove(id: &str) {
+    PENDING.with(|pending| pending.borrow_mut().remove(id));
+    if let Some(frames) = FRAMES.with(|frames| frames.borrow_mut().remove(id)) {
+        frames.iter().for_each(|frame| frame.close());
+    }
+}
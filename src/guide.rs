@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// The y-coordinate of the top edge of the horizontal ruler, just below the header
+pub const RULER_TOP: i16 = 23;
+/// The thickness of the rulers
+pub const RULER_SIZE: i16 = 16;
+
+/// A guide line that images snap to while they are dragged and resized, see `snap`. Guides are dragged out of the
+/// rulers and belong to a board.
+///
+/// - `Horizontal`&rarr; a horizontal line at the given y-coordinate, dragged out of the ruler at the top
+/// - `Vertical`&rarr; a vertical line at the given x-coordinate, dragged out of the ruler at the left
+///
+/// Implements the following functions:
+/// - `moved_to`&rarr; returns the guide at the position of the mouse
+/// - `is_on_ruler`&rarr; returns true if the guide lies on its ruler, guides that are dropped there are removed
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Guide {
+    Horizontal(i16),
+    Vertical(i16),
+}
+
+impl Guide {
+    /// Returns the guide moved to the position of the mouse; only the coordinate across the guide is used
+    pub fn moved_to(&self, x: i16, y: i16) -> Guide {
+        match &self {
+            Self::Horizontal(_) => Self::Horizontal(y),
+            Self::Vertical(_) => Self::Vertical(x),
+        }
+    }
+
+    /// Returns true if the guide lies on the ruler it has been dragged out of
+    pub fn is_on_ruler(&self) -> bool {
+        match &self {
+            Self::Horizontal(y) => *y < RULER_TOP + RULER_SIZE,
+            Self::Vertical(x) => *x < RULER_SIZE,
+        }
+    }
+}
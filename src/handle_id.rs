@@ -3,7 +3,7 @@ use yew::{html, virtual_dom::VNode, Html};
 
 use crate::components::handle::Handle;
 use crate::{bounding_box::BoundingBox, position::Position};
-const HANDLE_SIZE: i16 = 10;
+pub const HANDLE_SIZE: i16 = 10;
 
 /// This enum contains all resize handles for all 8 directions.
 ///
@@ -11,6 +11,9 @@ const HANDLE_SIZE: i16 = 10;
 /// - `to_string`&rarr; prints out a snake case id that can be used for the `id` attribute of the handle div
 /// - `get_position`&rarr; returns the position where the handle should be rendered on the image
 /// - `get_cursor`&rarr; returns the matching css style cursor type, based on the resize direction
+/// - `edges`&rarr; returns which edges of the image the handle moves
+/// - `calculate_bounding_box_from_centre`&rarr; like `calculate_bounding_box`, but the image is resized symmetrically
+/// about its centre
 /// - `get_bounding_box`&rarr; returns the x- and y-coordinate and the width and the height of the image container based on the new position of the handle
 /// - `into_iter`&rarr; turns the enum into an iterable
 /// - `get_html`&rarr; returns a html fragment that contains all 8 resize handles at their proper positions
@@ -86,8 +89,30 @@ impl HandleId {
         .to_string()
     }
 
+    /// returns which edges of the image the handle moves: -1 for the left or top edge, 1 for the right or bottom edge
+    /// and 0 if the handle doesn't move an edge along that axis
+    pub fn edges(&self) -> (i16, i16) {
+        match &self {
+            Self::TopLeft => (-1, -1),
+            Self::TopRight => (1, -1),
+            Self::BottomLeft => (-1, 1),
+            Self::BottomRight => (1, 1),
+            Self::MidRight => (1, 0),
+            Self::MidLeft => (-1, 0),
+            Self::MidTop => (0, -1),
+            Self::MidBottom => (0, 1),
+        }
+    }
+
     /// returns the x- and y-coordinate and the width and the height of the image container
     /// based on the new position of the handle
+    ///
+    /// The `anchor` is the position of the mouse within the handle when the user grabbed it, so the edges that the
+    /// handle moves follow the mouse without jumping. The other edges stay in place. With `keep_ratio` the handles in the
+    /// corners and the handles on the left and right edge set the width, and the height follows from the `ratio`; the
+    /// handles on the top and bottom edge set the height. A dimension that follows is aligned with the corner that stays
+    /// in place, or centred for the handles in the middle of an edge. A ratio that isn't a positive number, like the
+    /// ratio of an image without height, is ignored. The width and the height are at least 1.
     pub fn calculate_bounding_box(
         &self,
         img: BoundingBox,
@@ -98,168 +123,91 @@ impl HandleId {
     ) -> BoundingBox {
         // log!("mouse", mouse.to_string());
         // log!("anchor", anchor.to_string());
-        match &self {
-            Self::TopLeft => {
-                if keep_ratio {
-                    let width = img.width + (img.x - mouse.x + anchor.x);
-                    let height = (1.0 / ratio * width as f32) as i16;
-                    BoundingBox {
-                        x: mouse.x - anchor.x,
-                        y: img.y - (height - img.height),
-                        width,
-                        height,
-                    }
-                } else {
-                    BoundingBox {
-                        x: mouse.x - anchor.x,
-                        y: mouse.y - anchor.y,
-                        width: img.width + (img.x - mouse.x),
-                        height: img.height + (img.y - mouse.y),
-                    }
-                }
-            }
-
-            Self::BottomLeft => {
-                if keep_ratio {
-                    let width = img.width + (img.x - mouse.x + anchor.x);
-                    let height = (1.0 / ratio * width as f32) as i16;
-                    BoundingBox {
-                        x: mouse.x - anchor.x,
-                        y: img.y,
-                        width,
-                        height,
-                    }
-                } else {
-                    BoundingBox {
-                        x: mouse.x - anchor.x,
-                        y: img.y,
-                        width: img.width + (img.x - mouse.x + anchor.x),
-                        height: mouse.y - img.y + anchor.y,
-                    }
-                }
-            }
+        let (dir_x, dir_y) = self.edges();
+        let (mut left, mut right) = (img.x, img.x + img.width);
+        let (mut top, mut bottom) = (img.y, img.y + img.height);
+        // a handle lies inside the image, the handles on the right and bottom edge end at that edge
+        match dir_x {
+            -1 => left = mouse.x - anchor.x,
+            1 => right = mouse.x - anchor.x + HANDLE_SIZE,
+            _ => (),
+        }
+        match dir_y {
+            -1 => top = mouse.y - anchor.y,
+            1 => bottom = mouse.y - anchor.y + HANDLE_SIZE,
+            _ => (),
+        }
+        let mut width = (right - left).max(1);
+        let mut height = (bottom - top).max(1);
 
-            Self::TopRight => {
-                if keep_ratio {
-                    let width = mouse.x - img.x + anchor.x;
-                    let height = (1.0 / ratio * width as f32) as i16;
-                    BoundingBox {
-                        x: img.x,
-                        y: img.y - (height - img.height),
-                        width,
-                        height,
-                    }
-                } else {
-                    BoundingBox {
-                        x: img.x,
-                        y: mouse.y - anchor.y,
-                        width: mouse.x - img.x + anchor.x,
-                        height: img.height + (img.y - mouse.y),
-                    }
-                }
-            }
+        let keep_ratio = keep_ratio && ratio.is_finite() && ratio > 0.0;
+        if keep_ratio && dir_x != 0 {
+            height = ((width as f32 / ratio).round() as i16).max(1);
+        } else if keep_ratio {
+            width = ((height as f32 * ratio).round() as i16).max(1);
+        }
 
-            Self::BottomRight => {
-                if keep_ratio {
-                    let width = mouse.x - img.x + anchor.x;
-                    let height = (1.0 / ratio * width as f32) as i16;
-                    BoundingBox {
-                        x: img.x,
-                        y: img.y,
-                        width,
-                        height,
-                    }
-                } else {
-                    BoundingBox {
-                        x: img.x,
-                        y: img.y,
-                        width: mouse.x - img.x + anchor.x,
-                        height: mouse.y - img.y + anchor.y,
-                    }
-                }
-            }
+        // the edges that the handle moves are placed against the edges that stay in place
+        let x = match dir_x {
+            -1 => right - width,
+            1 => left,
+            _ => img.x - (width - img.width) / 2,
+        };
+        let y = match dir_y {
+            -1 => bottom - height,
+            1 => top,
+            _ => img.y - (height - img.height) / 2,
+        };
+        BoundingBox {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
 
-            Self::MidRight => {
-                if keep_ratio {
-                    let width = mouse.x - img.x + anchor.x;
-                    let height = (1.0 / ratio * width as f32) as i16;
-                    let diff = (height - img.height) / 2;
-                    BoundingBox {
-                        x: img.x,
-                        y: img.y - diff,
-                        width,
-                        height,
-                    }
-                } else {
-                    BoundingBox {
-                        x: img.x,
-                        y: img.y,
-                        width: mouse.x - img.x + anchor.x,
-                        height: img.height,
-                    }
-                }
-            }
-            Self::MidLeft => {
-                if keep_ratio {
-                    let width = img.width + (img.x - mouse.x + anchor.x);
-                    let height = (1.0 / ratio * width as f32) as i16;
-                    let diff = (height - img.height) / 2;
-                    BoundingBox {
-                        x: mouse.x - anchor.x,
-                        y: img.y - diff,
-                        width,
-                        height,
-                    }
-                } else {
-                    BoundingBox {
-                        x: mouse.x - anchor.x,
-                        y: img.y,
-                        width: img.width + (img.x - mouse.x),
-                        height: img.height,
-                    }
-                }
-            }
-            Self::MidTop => {
-                if keep_ratio {
-                    let height = img.height + (img.y - mouse.y);
-                    let width = (ratio * height as f32) as i16;
-                    let diff = (width - img.width) / 2;
-                    BoundingBox {
-                        x: img.x - diff,
-                        y: mouse.y - anchor.y,
-                        width,
-                        height,
-                    }
-                } else {
-                    BoundingBox {
-                        x: img.x,
-                        y: mouse.y - anchor.y,
-                        width: img.width,
-                        height: img.height + (img.y - mouse.y),
-                    }
-                }
-            }
-            Self::MidBottom => {
-                if keep_ratio {
-                    let height = img.height + (mouse.y - img.y - img.height);
-                    let width = (ratio * height as f32) as i16;
-                    let diff = (width - img.width) / 2;
-                    BoundingBox {
-                        x: img.x - diff,
-                        y: img.y,
-                        width,
-                        height,
-                    }
-                } else {
-                    BoundingBox {
-                        x: img.x,
-                        y: img.y,
-                        width: img.width,
-                        height: img.height + (mouse.y - img.y - img.height),
-                    }
-                }
+    /// Returns the bounding box of the image when it is resized about its centre, like with the `alt` key held down in
+    /// design tools: the opposite edge moves as far as the edge that is dragged, but in the other direction, so that the
+    /// centre of the image stays in place. With `keep_ratio` the dimension that the handle doesn't move follows from the
+    /// dimension it does move, also for the handles in the middle of an edge. The width and the height are at least 1.
+    pub fn calculate_bounding_box_from_centre(
+        &self,
+        img: BoundingBox,
+        anchor: Position,
+        mouse: Position,
+        ratio: f32,
+        keep_ratio: bool,
+    ) -> BoundingBox {
+        let bb = self.calculate_bounding_box(img, anchor, mouse, ratio, keep_ratio);
+        let (dir_x, dir_y) = self.edges();
+        // the opposite edge moves as well, so large drags can exceed the range of an `i16`
+        let mut width = if dir_x != 0 {
+            img.width
+                .saturating_add(bb.width.saturating_sub(img.width).saturating_mul(2))
+        } else {
+            bb.width
+        };
+        let mut height = if dir_y != 0 {
+            img.height
+                .saturating_add(bb.height.saturating_sub(img.height).saturating_mul(2))
+        } else {
+            bb.height
+        };
+        // an image without width or height has no ratio to keep
+        if keep_ratio && ratio.is_finite() && ratio > 0.0 {
+            if dir_x != 0 {
+                height = (width as f32 / ratio).round() as i16;
+            } else {
+                width = (height as f32 * ratio).round() as i16;
             }
         }
+        let (width, height) = (width.max(1), height.max(1));
+        BoundingBox {
+            x: img.x - (width / 2 - img.width / 2),
+            y: img.y - (height / 2 - img.height / 2),
+            width,
+            height,
+        }
     }
 
     /// Turns the enum into an iterable
@@ -298,3 +246,309 @@ impl HandleId {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The number of random cases every property is checked with
+    const CASES: usize = 1000;
+
+    /// A xorshift generator with a fixed seed, so the property tests are repeatable without extra dependencies
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// returns a number from `min` up to and including `max`
+        fn range(&mut self, min: i16, max: i16) -> i16 {
+            min + (self.next() % (max - min + 1) as u64) as i16
+        }
+    }
+
+    fn image() -> BoundingBox {
+        BoundingBox {
+            x: 100,
+            y: 50,
+            width: 300,
+            height: 200,
+        }
+    }
+
+    fn random_image(rng: &mut Rng) -> BoundingBox {
+        BoundingBox {
+            x: rng.range(-500, 500),
+            y: rng.range(-500, 500),
+            width: rng.range(4 * HANDLE_SIZE, 800),
+            height: rng.range(4 * HANDLE_SIZE, 800),
+        }
+    }
+
+    /// Returns the position of the mouse when the user grabs the handle at `anchor` within the handle
+    fn grab(handle: &HandleId, img: BoundingBox, anchor: Position) -> Position {
+        let (_, x, y) = handle.get_position(img.width, img.height);
+        Position {
+            x: img.x + x + anchor.x,
+            y: img.y + y + anchor.y,
+        }
+    }
+
+    fn moved(position: Position, dx: i16, dy: i16) -> Position {
+        Position {
+            x: position.x + dx,
+            y: position.y + dy,
+        }
+    }
+
+    fn ratio(img: BoundingBox) -> f32 {
+        img.width as f32 / img.height as f32
+    }
+
+    #[test]
+    fn grabbing_a_handle_without_moving_keeps_the_image() {
+        let img = image();
+        for handle in HandleId::into_iter() {
+            for (ax, ay) in [(0, 0), (5, 5), (9, 0), (0, 9), (9, 9)] {
+                let anchor = Position { x: ax, y: ay };
+                let mouse = grab(&handle, img, anchor);
+                for keep_ratio in [false, true] {
+                    let bb =
+                        handle.calculate_bounding_box(img, anchor, mouse, ratio(img), keep_ratio);
+                    assert!(
+                        bb == img,
+                        "{} keep_ratio {} anchor {}: {}",
+                        handle,
+                        keep_ratio,
+                        anchor,
+                        bb
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn left_handles_use_the_anchor_alike() {
+        // the top left handle used to ignore the anchor in free mode, unlike the bottom left handle
+        let img = image();
+        let anchor = Position { x: 7, y: 3 };
+        let top_left = HandleId::TopLeft.calculate_bounding_box(
+            img,
+            anchor,
+            moved(grab(&HandleId::TopLeft, img, anchor), -20, 0),
+            ratio(img),
+            false,
+        );
+        let bottom_left = HandleId::BottomLeft.calculate_bounding_box(
+            img,
+            anchor,
+            moved(grab(&HandleId::BottomLeft, img, anchor), -20, 0),
+            ratio(img),
+            false,
+        );
+        assert_eq!(top_left.x, 80);
+        assert_eq!(top_left.width, 320);
+        assert_eq!(bottom_left.x, top_left.x);
+        assert_eq!(bottom_left.width, top_left.width);
+    }
+
+    #[test]
+    fn free_resize_moves_the_edges_of_the_handle_with_the_mouse() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..CASES {
+            let img = random_image(&mut rng);
+            let anchor = Position {
+                x: rng.range(0, HANDLE_SIZE - 1),
+                y: rng.range(0, HANDLE_SIZE - 1),
+            };
+            // the image never gets smaller than a handle, so no edge passes the opposite edge
+            let dx = rng.range(-(img.width - HANDLE_SIZE), 300);
+            let dy = rng.range(-(img.height - HANDLE_SIZE), 300);
+            for handle in HandleId::into_iter() {
+                let mouse = grab(&handle, img, anchor);
+                let (dir_x, dir_y) = handle.edges();
+                let bb = handle.calculate_bounding_box(
+                    img,
+                    anchor,
+                    moved(mouse, dir_x * dx, dir_y * dy),
+                    ratio(img),
+                    false,
+                );
+                let expected = BoundingBox {
+                    x: if dir_x < 0 { img.x - dx } else { img.x },
+                    y: if dir_y < 0 { img.y - dy } else { img.y },
+                    width: img.width + dir_x.abs() * dx,
+                    height: img.height + dir_y.abs() * dy,
+                };
+                assert!(bb == expected, "{} {}: {} != {}", handle, img, bb, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn keeping_the_ratio_keeps_the_ratio_and_the_opposite_corner() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..CASES {
+            let img = random_image(&mut rng);
+            let anchor = Position {
+                x: rng.range(0, HANDLE_SIZE - 1),
+                y: rng.range(0, HANDLE_SIZE - 1),
+            };
+            let dx = rng.range(-(img.width - HANDLE_SIZE), 300);
+            let dy = rng.range(-(img.height - HANDLE_SIZE), 300);
+            for handle in HandleId::into_iter() {
+                let mouse = moved(grab(&handle, img, anchor), dx, dy);
+                let bb = handle.calculate_bounding_box(img, anchor, mouse, ratio(img), true);
+                let (dir_x, dir_y) = handle.edges();
+                // the dimension that follows is rounded to whole pixels, the result is never smaller than 1
+                if dir_x != 0 {
+                    let height = (bb.width as f32 / ratio(img)).round().max(1.) as i16;
+                    assert_eq!(bb.height, height, "{} {}: {}", handle, img, bb);
+                } else {
+                    let width = (bb.height as f32 * ratio(img)).round().max(1.) as i16;
+                    assert_eq!(bb.width, width, "{} {}: {}", handle, img, bb);
+                }
+                let (right, bottom) = (img.x + img.width, img.y + img.height);
+                match dir_x {
+                    -1 => assert_eq!(bb.x + bb.width, right, "{} {}: {}", handle, img, bb),
+                    1 => assert_eq!(bb.x, img.x, "{} {}: {}", handle, img, bb),
+                    _ => assert!((2 * bb.x + bb.width - 2 * img.x - img.width).abs() <= 1),
+                }
+                match dir_y {
+                    -1 => assert_eq!(bb.y + bb.height, bottom, "{} {}: {}", handle, img, bb),
+                    1 => assert_eq!(bb.y, img.y, "{} {}: {}", handle, img, bb),
+                    _ => assert!((2 * bb.y + bb.height - 2 * img.y - img.height).abs() <= 1),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn resizing_from_the_centre_keeps_the_centre() {
+        let mut rng = Rng(0xdead_beef_cafe_f00d);
+        for _ in 0..CASES {
+            let img = random_image(&mut rng);
+            let anchor = Position {
+                x: rng.range(0, HANDLE_SIZE - 1),
+                y: rng.range(0, HANDLE_SIZE - 1),
+            };
+            let dx = rng.range(-(img.width / 2 - HANDLE_SIZE), 300);
+            let dy = rng.range(-(img.height / 2 - HANDLE_SIZE), 300);
+            for handle in HandleId::into_iter() {
+                let (dir_x, dir_y) = handle.edges();
+                let mouse = moved(grab(&handle, img, anchor), dir_x * dx, dir_y * dy);
+                for keep_ratio in [false, true] {
+                    let bb = handle.calculate_bounding_box_from_centre(
+                        img,
+                        anchor,
+                        mouse,
+                        ratio(img),
+                        keep_ratio,
+                    );
+                    assert!((2 * bb.x + bb.width - 2 * img.x - img.width).abs() <= 1);
+                    assert!((2 * bb.y + bb.height - 2 * img.y - img.height).abs() <= 1);
+                    if !keep_ratio {
+                        assert_eq!(bb.width, img.width + 2 * dir_x.abs() * dx);
+                        assert_eq!(bb.height, img.height + 2 * dir_y.abs() * dy);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn the_size_is_at_least_one_pixel() {
+        let mut rng = Rng(0x0123_4567_89ab_cdef);
+        for _ in 0..CASES {
+            let img = random_image(&mut rng);
+            let mouse = Position {
+                x: rng.range(-2000, 2000),
+                y: rng.range(-2000, 2000),
+            };
+            for handle in HandleId::into_iter() {
+                for keep_ratio in [false, true] {
+                    let anchor = Position { x: 5, y: 5 };
+                    let bb =
+                        handle.calculate_bounding_box(img, anchor, mouse, ratio(img), keep_ratio);
+                    assert!(
+                        bb.width >= 1 && bb.height >= 1,
+                        "{} {}: {}",
+                        handle,
+                        img,
+                        bb
+                    );
+                    let bb = handle.calculate_bounding_box_from_centre(
+                        img,
+                        anchor,
+                        mouse,
+                        ratio(img),
+                        keep_ratio,
+                    );
+                    assert!(
+                        bb.width >= 1 && bb.height >= 1,
+                        "{} {}: {}",
+                        handle,
+                        img,
+                        bb
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn an_image_without_height_can_be_resized() {
+        // the ratio of an image without height is infinite, and not a number if the image has no width either
+        for img in [
+            BoundingBox {
+                x: 10,
+                y: 10,
+                width: 100,
+                height: 0,
+            },
+            BoundingBox {
+                x: 10,
+                y: 10,
+                width: 0,
+                height: 0,
+            },
+        ] {
+            let anchor = Position { x: 5, y: 5 };
+            for handle in HandleId::into_iter() {
+                let mouse = moved(grab(&handle, img, anchor), 20, 20);
+                for keep_ratio in [false, true] {
+                    for bb in [
+                        handle.calculate_bounding_box(img, anchor, mouse, ratio(img), keep_ratio),
+                        handle.calculate_bounding_box_from_centre(
+                            img,
+                            anchor,
+                            mouse,
+                            ratio(img),
+                            keep_ratio,
+                        ),
+                    ] {
+                        assert!(
+                            bb.width >= 1 && bb.height >= 1,
+                            "{} {}: {}",
+                            handle,
+                            img,
+                            bb
+                        );
+                        assert!(
+                            bb.width < 1000 && bb.height < 1000,
+                            "{} {}: {}",
+                            handle,
+                            img,
+                            bb
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
+
+use crate::{
+    app_state::ImageData,
+    bounding_box::BoundingBox,
+    handle_id::{HandleId, HANDLE_SIZE},
+    pattern_repeat::PatternRepeat,
+    position::Position,
+};
+
+/// The maximum width and height of an alpha mask, larger images are sampled at a lower resolution
+const MAX_MASK_SIZE: u32 = 256;
+/// Pixels with an alpha value up to this value are treated as transparent
+const ALPHA_THRESHOLD: u8 = 10;
+
+thread_local! {
+    /// The alpha masks of all loaded images, stored by image id
+    static ALPHA_MASKS: RefCell<HashMap<String, AlphaMask>> = RefCell::new(HashMap::new());
+}
+
+/// Struct that stores the alpha channel of an image, sampled at a resolution of at most 256 by 256 pixels.
+pub struct AlphaMask {
+    pub width: u32,
+    pub height: u32,
+    pub alpha: Vec<u8>,
+}
+
+impl AlphaMask {
+    /// Draws the image onto an offscreen canvas and reads back the alpha channel. Returns `None` if the pixels of the
+    /// image can't be read, for instance because the image is loaded from another origin.
+    pub fn from_image(img_element: &HtmlImageElement) -> Option<AlphaMask> {
+        let natural_width = img_element.natural_width();
+        let natural_height = img_element.natural_height();
+        if natural_width == 0 || natural_height == 0 {
+            return None;
+        }
+        let width = natural_width.min(MAX_MASK_SIZE);
+        let height = natural_height.min(MAX_MASK_SIZE);
+
+        let document = web_sys::window()?.document()?;
+        let canvas = document
+            .create_element("canvas")
+            .ok()?
+            .dyn_into::<HtmlCanvasElement>()
+            .ok()?;
+        canvas.set_width(width);
+        canvas.set_height(height);
+        let ctx = canvas
+            .get_context("2d")
+            .ok()??
+            .dyn_into::<CanvasRenderingContext2d>()
+            .ok()?;
+        ctx.draw_image_with_html_image_element_and_dw_and_dh(
+            img_element,
+            0.,
+            0.,
+            width as f64,
+            height as f64,
+        )
+        .ok()?;
+        let pixels = ctx
+            .get_image_data(0., 0., width as f64, height as f64)
+            .ok()?
+            .data();
+        let alpha = pixels.iter().skip(3).step_by(4).copied().collect();
+
+        Some(AlphaMask {
+            width,
+            height,
+            alpha,
+        })
+    }
+
+    /// returns true if the pixel at the normalized coordinates `u`, `v` (both between 0 and 1) is opaque
+    fn is_opaque(&self, u: f32, v: f32) -> bool {
+        let x = ((u * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v * self.height as f32) as u32).min(self.height - 1);
+        self.alpha[(y * self.width + x) as usize] > ALPHA_THRESHOLD
+    }
+}
+
+/// Stores the alpha mask of an image so that hit tests can ignore the transparent areas of the image
+pub fn set_alpha_mask(id: &str, mask: AlphaMask) {
+    ALPHA_MASKS.with(|masks| masks.borrow_mut().insert(id.to_string(), mask));
+}
+
+/// Removes the alpha mask of an image
+pub fn remove_alpha_mask(id: &str) {
+    ALPHA_MASKS.with(|masks| masks.borrow_mut().remove(id));
+}
+
+/// Maps a coordinate in tile units onto the tile, taking the repeat mode and mirroring into account. Returns
+/// `None` if the coordinate falls outside of the area that is covered by the pattern.
+fn wrap(t: f32, repeat: bool, mirror: bool) -> Option<f32> {
+    let period = if mirror { 2.0 } else { 1.0 };
+    let t = if repeat {
+        t.rem_euclid(period)
+    } else if (0.0..period).contains(&t) {
+        t
+    } else {
+        return None;
+    };
+    Some(if t >= 1.0 { 2.0 - t } else { t })
+}
+
+/// Returns the normalized coordinates on the source image of the pixel that is drawn at position `x`, `y` relative to
+/// the image. For patterns the inverse of the pattern transform is applied. Returns `None` if no pixel of the source
+/// image is drawn at this position.
+fn source_coordinates(img: &ImageData, x: f32, y: f32) -> Option<(f32, f32)> {
+    if !img.use_pattern {
+        return Some((x / img.width as f32, y / img.height as f32));
+    }
+    if img.pattern_width <= 0 || img.pattern_height <= 0 {
+        return None;
+    }
+    let dx = x - img.pattern_x as f32;
+    let dy = y - img.pattern_y as f32;
+    let (sin, cos) = (img.pattern_rotation as f32).to_radians().sin_cos();
+    let u = (dx * cos + dy * sin) / img.pattern_width as f32;
+    let v = (dy * cos - dx * sin) / img.pattern_height as f32;
+    let (repeat_x, repeat_y) = match img.pattern_repeat {
+        PatternRepeat::Repeat => (true, true),
+        PatternRepeat::RepeatX => (true, false),
+        PatternRepeat::RepeatY => (false, true),
+        PatternRepeat::NoRepeat => (false, false),
+    };
+    Some((
+        wrap(u, repeat_x, img.pattern_mirror)?,
+        wrap(v, repeat_y, img.pattern_mirror)?,
+    ))
+}
+
+/// returns true if the image is hit at the position, if `use_alpha` is set the transparent areas of the image are
+/// not considered a hit
+fn is_hit(img: &ImageData, position: Position, use_alpha: bool) -> bool {
+    if !img.bounding_box().contains(position) {
+        return false;
+    }
+    if !use_alpha {
+        return true;
+    }
+    let x = (position.x - img.x) as f32;
+    let y = (position.y - img.y) as f32;
+    ALPHA_MASKS.with(|masks| match masks.borrow().get(&img.id) {
+        Some(mask) => source_coordinates(img, x, y).map_or(false, |(u, v)| mask.is_opaque(u, v)),
+        // without alpha mask the image is treated as if it were fully opaque
+        None => true,
+    })
+}
+
+/// Returns the indices of the images sorted from top to bottom. Images with a higher z-index are on top, images with
+/// the same z-index are stacked in the order they were added.
+pub fn top_to_bottom(images: &[ImageData]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..images.len()).collect();
+    indices.sort_by_key(|i| images[*i].z_index);
+    indices.reverse();
+    indices
+}
+
+/// Returns the index of the image that is on top at the given position. If `use_alpha` is set, clicks on the
+/// transparent areas of an image fall through to the images below.
+pub fn hit_test(images: &[ImageData], position: Position, use_alpha: bool) -> Option<usize> {
+    top_to_bottom(images)
+        .into_iter()
+        .find(|i| images[*i].width > 0 && is_hit(&images[*i], position, use_alpha))
+}
+
+/// Returns the id of the resize handle of the image at the given position
+pub fn hit_test_handle(img: &ImageData, position: Position) -> Option<HandleId> {
+    HandleId::into_iter().find(|handle| {
+        let (_, x, y) = handle.get_position(img.width, img.height);
+        BoundingBox {
+            x: img.x + x,
+            y: img.y + y,
+            width: HANDLE_SIZE,
+            height: HANDLE_SIZE,
+        }
+        .contains(position)
+    })
+}
+
+/// Returns the indices of all images that overlap with the rectangle, used for marquee selection
+pub fn images_in_rect(images: &[ImageData], rect: BoundingBox) -> Vec<usize> {
+    top_to_bottom(images)
+        .into_iter()
+        .filter(|i| images[*i].width > 0 && images[*i].bounding_box().intersects(&rect))
+        .collect()
+}
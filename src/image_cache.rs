@@ -0,0 +1,196 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
+use gloo_console::log;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, ImageBitmap, ImageBitmapOptions,
+    ResizeQuality,
+};
+
+use crate::orientation::Orientation;
+
+/// Levels are created until the next level would be smaller than this size in either direction
+const MIN_LEVEL_SIZE: u32 = 32;
+
+thread_local! {
+    /// The decoded images stored by image id. The first level is the original image, every next level is half the
+    /// width and half the height of the level before.
+    static LEVELS: RefCell<HashMap<String, Vec<ImageBitmap>>> = RefCell::new(HashMap::new());
+    /// The ids of the images that are being decoded
+    static PENDING: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+    /// The last rasterized copy of every vector image, stored by image id
+    static RASTERS: RefCell<HashMap<String, HtmlCanvasElement>> = RefCell::new(HashMap::new());
+}
+
+/// Decodes the image once into an ImageBitmap and creates the downscaled levels from it. The decoding happens
+/// asynchronously, `on_decoded` is called as soon as decoding has finished; if decoding fails the image element itself
+/// keeps being drawn. Images that are already decoded, or that are being decoded, are skipped.
+///
+/// If the browser doesn't show photos upright by itself, the EXIF `orientation` is applied to the original level, so all
+/// levels are upright.
+pub fn decode(
+    id: &str,
+    img_element: &HtmlImageElement,
+    orientation: Orientation,
+    on_decoded: impl FnOnce() + 'static,
+) {
+    let is_known = LEVELS.with(|levels| levels.borrow().contains_key(id))
+        || !PENDING.with(|pending| pending.borrow_mut().insert(id.to_string()));
+    if is_known {
+        return;
+    }
+
+    let id = id.to_string();
+    let img_element = img_element.clone();
+    spawn_local(async move {
+        let result = create_levels(&img_element, orientation).await;
+        // the image may have been removed while it was being decoded
+        let is_removed = !PENDING.with(|pending| pending.borrow_mut().remove(&id));
+        match result {
+            Ok(new_levels) if is_removed => new_levels.iter().for_each(|level| level.close()),
+            Ok(new_levels) => {
+                LEVELS.with(|levels| levels.borrow_mut().insert(id, new_levels));
+                on_decoded();
+            }
+            Err(e) => {
+                log!("error decode image", e);
+                if !is_removed {
+                    on_decoded();
+                }
+            }
+        }
+    });
+}
+
+/// Draws the image upright onto a canvas, the canvas is used as the source of the original level
+fn create_upright_canvas(
+    img_element: &HtmlImageElement,
+    orientation: Orientation,
+) -> Result<HtmlCanvasElement, JsValue> {
+    let width = img_element.natural_width();
+    let height = img_element.natural_height();
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    if orientation.swaps_axes() {
+        canvas.set_width(height);
+        canvas.set_height(width);
+    } else {
+        canvas.set_width(width);
+        canvas.set_height(height);
+    }
+    let ctx = canvas
+        .get_context("2d")?
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    let [a, b, c, d, e, f] = orientation.transform(width as f64, height as f64);
+    ctx.transform(a, b, c, d, e, f)?;
+    ctx.draw_image_with_html_image_element(img_element, 0., 0.)?;
+    Ok(canvas)
+}
+
+/// Creates the original ImageBitmap from the image element and halves it until the minimal level size is reached
+async fn create_levels(
+    img_element: &HtmlImageElement,
+    orientation: Orientation,
+) -> Result<Vec<ImageBitmap>, JsValue> {
+    let window = web_sys::window().unwrap();
+    let promise = if orientation == Orientation::Normal {
+        window.create_image_bitmap_with_html_image_element(img_element)?
+    } else {
+        let canvas = create_upright_canvas(img_element, orientation)?;
+        window.create_image_bitmap_with_html_canvas_element(&canvas)?
+    };
+    let original = JsFuture::from(promise).await?.dyn_into::<ImageBitmap>()?;
+
+    let mut width = original.width();
+    let mut height = original.height();
+    let mut levels = vec![original];
+    while width / 2 >= MIN_LEVEL_SIZE && height / 2 >= MIN_LEVEL_SIZE {
+        width /= 2;
+        height /= 2;
+        let mut options = ImageBitmapOptions::new();
+        options
+            .resize_width(width)
+            .resize_height(height)
+            .resize_quality(ResizeQuality::High);
+        // every level is created from the level before, which gives a better quality than downscaling the original
+        let previous = levels.last().unwrap();
+        let promise = window
+            .create_image_bitmap_with_image_bitmap_and_image_bitmap_options(previous, &options)?;
+        levels.push(JsFuture::from(promise).await?.dyn_into::<ImageBitmap>()?);
+    }
+    Ok(levels)
+}
+
+/// Returns the smallest level that covers the given size. If no level is large enough the original is returned, if the
+/// image hasn't been decoded yet `None` is returned.
+pub fn get_level(id: &str, width: f64, height: f64) -> Option<ImageBitmap> {
+    LEVELS.with(|levels| {
+        let levels = levels.borrow();
+        let levels = levels.get(id)?;
+        levels
+            .iter()
+            .rev()
+            .find(|level| level.width() as f64 >= width && level.height() as f64 >= height)
+            .or(levels.first())
+            .cloned()
+    })
+}
+
+/// Returns a copy of a vector image that is rasterized at the given size. Vector images are not decoded into levels
+/// because these would be blurry when they are enlarged; instead the image is rasterized again whenever the size changes.
+/// The last copy is kept, so as long as the size doesn't change the image is not rasterized again.
+pub fn get_raster(
+    id: &str,
+    img_element: &HtmlImageElement,
+    width: u32,
+    height: u32,
+) -> Result<HtmlCanvasElement, JsValue> {
+    let width = width.max(1);
+    let height = height.max(1);
+    let cached = RASTERS.with(|rasters| {
+        rasters
+            .borrow()
+            .get(id)
+            .filter(|canvas| canvas.width() == width && canvas.height() == height)
+            .cloned()
+    });
+    if let Some(canvas) = cached {
+        return Ok(canvas);
+    }
+
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let ctx = canvas
+        .get_context("2d")?
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    ctx.draw_image_with_html_image_element_and_dw_and_dh(
+        img_element,
+        0.,
+        0.,
+        width as f64,
+        height as f64,
+    )?;
+    RASTERS.with(|rasters| rasters.borrow_mut().insert(id.to_string(), canvas.clone()));
+    Ok(canvas)
+}
+
+/// Releases the decoded levels and the rasterized copy of an image
+pub fn remove(id: &str) {
+    PENDING.with(|pending| pending.borrow_mut().remove(id));
+    RASTERS.with(|rasters| rasters.borrow_mut().remove(id));
+    if let Some(levels) = LEVELS.with(|levels| levels.borrow_mut().remove(id)) {
+        levels.iter().for_each(|level| level.close());
+    }
+}
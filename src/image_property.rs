@@ -0,0 +1,66 @@
+use std::fmt;
+
+use crate::artboard::MAX_SIZE;
+
+/// The largest distance of an image from the origin of the stage that can be entered
+const MAX_POSITION: i16 = 10_000;
+
+/// This enum contains the properties of an image that can be edited in the inspector.
+///
+/// - `X`, `Y`&rarr; the position of the top left corner of the image
+/// - `Width`, `Height`&rarr; the size of the image, or of the surface of a pattern
+/// - `TileWidth`, `TileHeight`&rarr; the size of the tile of a pattern
+/// - `ZIndex`&rarr; the stacking order of the image
+///
+/// Implements the following functions:
+/// - `to_string`&rarr; prints out a label that can be used in the user interface
+/// - `range`&rarr; returns the smallest and the largest value of the property
+/// - `parse`&rarr; parses the text that the user has entered, values outside the range are rejected
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImageProperty {
+    X,
+    Y,
+    Width,
+    Height,
+    TileWidth,
+    TileHeight,
+    ZIndex,
+}
+
+/// prints out a label that can be used in the user interface
+impl fmt::Display for ImageProperty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = match &self {
+            Self::X => "x",
+            Self::Y => "y",
+            Self::Width => "width",
+            Self::Height => "height",
+            Self::TileWidth => "tile width",
+            Self::TileHeight => "tile height",
+            Self::ZIndex => "z-index",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl ImageProperty {
+    /// returns the smallest and the largest value of the property
+    pub fn range(&self) -> (i16, i16) {
+        match &self {
+            Self::X | Self::Y => (-MAX_POSITION, MAX_POSITION),
+            Self::Width | Self::Height | Self::TileWidth | Self::TileHeight => (1, MAX_SIZE),
+            Self::ZIndex => (0, MAX_SIZE),
+        }
+    }
+
+    /// Parses the text that the user has entered. Returns `None` if the text isn't a whole number or if the number lies
+    /// outside the range of the property.
+    pub fn parse(&self, input: &str) -> Option<i16> {
+        let (min, max) = self.range();
+        input
+            .trim()
+            .parse::<i16>()
+            .ok()
+            .filter(|value| (min..=max).contains(value))
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// This enum contains the stages an image goes through after it has been added.
+///
+/// - `Loading`&rarr; the image is being loaded, a spinner is shown
+/// - `Loaded`&rarr; the image has loaded and its dimensions are known
+/// - `Broken`&rarr; the image could not be loaded, a placeholder is shown with the option to retry or to remove the image
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ImageStatus {
+    #[default]
+    Loading,
+    Loaded,
+    Broken,
+}
@@ -0,0 +1,296 @@
+use std::fmt;
+
+use js_sys::Uint8Array;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::File;
+
+use crate::{
+    exif::{parse_jpeg, Exif},
+    object_urls,
+    orientation::Orientation,
+};
+
+/// The image formats that can be imported
+pub const ALLOWED_TYPES: [&str; 7] = [
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "image/svg+xml",
+    "image/avif",
+    "image/bmp",
+];
+
+/// The maximum size of an imported file in bytes
+pub const MAX_FILE_SIZE: f64 = 50.0 * 1024.0 * 1024.0;
+
+/// The number of bytes at the start of a JPEG file that are read to find the EXIF data
+const EXIF_HEADER_SIZE: i32 = 128 * 1024;
+
+/// Urls in error messages are shortened to this number of characters, data uris can be very long
+const MAX_URL_LENGTH: usize = 60;
+
+/// An image that has been imported and can be added to the scene:
+///
+/// - `url`&rarr; The object url of an imported file or the url of a dropped image
+/// - `exif`&rarr; The EXIF data of an imported JPEG file
+/// - `orientation`&rarr; The orientation that has to be applied by the app itself, see `orientation_to_apply`
+/// - `is_vector`&rarr; Is set for SVG images, these are rasterized at the size they are drawn
+#[derive(Default, Clone, PartialEq)]
+pub struct ImportedImage {
+    pub url: String,
+    pub exif: Option<Exif>,
+    pub orientation: Orientation,
+    pub is_vector: bool,
+}
+
+/// This enum contains the reasons why a file or a dropped url can't be imported.
+///
+/// The `to_string` function prints out a message that can be shown to the user.
+pub enum ImportError {
+    UnsupportedType(String, String),
+    TooLarge(String, f64),
+    ObjectUrl(String),
+    UnsupportedUrl(String),
+    NoImage,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            Self::UnsupportedType(name, file_type) if file_type.is_empty() => {
+                write!(f, "{}: unknown file type", name)
+            }
+            Self::UnsupportedType(name, file_type) => {
+                write!(f, "{}: {} is not supported", name, file_type)
+            }
+            Self::TooLarge(name, size) => write!(
+                f,
+                "{}: {:.1} MB exceeds the limit of {:.0} MB",
+                name,
+                size / 1024.0 / 1024.0,
+                MAX_FILE_SIZE / 1024.0 / 1024.0
+            ),
+            Self::ObjectUrl(name) => write!(f, "{}: could not be read", name),
+            Self::UnsupportedUrl(url) => write!(f, "{}: is not an image url", shorten(url)),
+            Self::NoImage => write!(f, "the dropped content doesn't contain an image"),
+        }
+    }
+}
+
+/// Shortens a url so that it can be shown in a message
+pub fn shorten(url: &str) -> String {
+    if url.chars().count() > MAX_URL_LENGTH {
+        url.chars().take(MAX_URL_LENGTH).collect::<String>() + "..."
+    } else {
+        url.to_string()
+    }
+}
+
+/// Returns the value for the `accept` attribute of a file input
+pub fn accept() -> String {
+    ALLOWED_TYPES.join(", ")
+}
+
+/// Returns the mime type of a file. Some platforms don't report the type of less common formats, in that case the
+/// type is derived from the extension of the file name.
+fn mime_type(name: &str, file_type: &str) -> String {
+    if !file_type.is_empty() {
+        return file_type.to_string();
+    }
+    let extension = name.rsplit('.').next().unwrap_or_default().to_lowercase();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        _ => "",
+    }
+    .to_string()
+}
+
+/// Reads the EXIF data from the start of a JPEG file
+async fn read_exif(file: &File) -> Option<Exif> {
+    let header = file.slice_with_i32_and_i32(0, EXIF_HEADER_SIZE).ok()?;
+    let buffer = JsFuture::from(header.array_buffer()).await.ok()?;
+    parse_jpeg(&Uint8Array::new(&buffer).to_vec())
+}
+
+/// Browsers that support the css property `image-orientation` show photos upright by themselves: both the natural size
+/// of the image element and drawing the image onto a canvas take the EXIF orientation into account. In other browsers
+/// the orientation is applied by the app when the image is decoded, see `image_cache`.
+fn orientation_to_apply(exif: &Option<Exif>) -> Orientation {
+    let applied_by_browser =
+        web_sys::css::supports_with_value("image-orientation", "from-image").unwrap_or(false);
+    match exif {
+        Some(exif) if !applied_by_browser => exif.orientation,
+        _ => Orientation::Normal,
+    }
+}
+
+/// Checks the type and the size of a file, creates an object url for the file and reads the EXIF data of JPEG files
+async fn import_file(file: &File) -> Result<ImportedImage, ImportError> {
+    let name = file.name();
+    let file_type = mime_type(&name, &file.type_());
+    if !ALLOWED_TYPES.contains(&file_type.as_str()) {
+        return Err(ImportError::UnsupportedType(name, file_type));
+    }
+    if file.size() > MAX_FILE_SIZE {
+        return Err(ImportError::TooLarge(name, file.size()));
+    }
+    // a file that is imported again while it is still used shares the object url
+    let source = format!("{}:{}:{}", name, file.size(), file.last_modified());
+    let url = object_urls::create(file, &source).map_err(|_| ImportError::ObjectUrl(name))?;
+    let exif = if file_type == "image/jpeg" {
+        read_exif(file).await
+    } else {
+        None
+    };
+    Ok(ImportedImage {
+        url,
+        orientation: orientation_to_apply(&exif),
+        exif,
+        is_vector: file_type == "image/svg+xml",
+    })
+}
+
+/// Imports dropped or opened files. Returns the images that have been imported and the errors of the files that
+/// couldn't be imported.
+pub async fn import_files(files: &[File]) -> (Vec<ImportedImage>, Vec<ImportError>) {
+    let mut images = Vec::new();
+    let mut errors = Vec::new();
+    for file in files {
+        match import_file(file).await {
+            Ok(image) => images.push(image),
+            Err(e) => errors.push(e),
+        }
+    }
+    (images, errors)
+}
+
+/// Returns true if the url refers to another server. These images are loaded with cors so that they don't taint the
+/// canvas, see `Msg::ImageFailed`.
+pub fn is_remote(url: &str) -> bool {
+    let url = url.to_ascii_lowercase();
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Returns true if a dropped url refers to an SVG image: a data uri with the SVG type or a url with the extension `.svg`
+fn is_vector_url(url: &str) -> bool {
+    let url = url.to_ascii_lowercase();
+    if let Some(data) = url.strip_prefix("data:") {
+        return data.starts_with("image/svg+xml");
+    }
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.ends_with(".svg")
+}
+
+/// Replaces the html entities that are commonly found in urls
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Returns the value of an attribute of a html tag. `tag_lower` is the ascii lowercase version of `tag`, so the
+/// byte offsets of both strings are the same.
+fn attribute(tag: &str, tag_lower: &str, name: &str) -> Option<String> {
+    let mut from = 0;
+    while let Some(i) = tag_lower[from..].find(name) {
+        let i = from + i;
+        from = i + name.len();
+        // skips attributes that end with the name, like `data-src`, and attributes that start with the name, like `srcset`
+        let after = tag_lower[from..].trim_start();
+        if !tag_lower[..i].ends_with(char::is_whitespace) || !after.starts_with('=') {
+            continue;
+        }
+        let value = tag[tag.len() - after.len() + 1..].trim_start();
+        let value = match value.chars().next()? {
+            quote @ ('"' | '\'') => value[1..].split(quote).next()?,
+            _ => value.split(char::is_whitespace).next()?,
+        };
+        return Some(decode_entities(value));
+    }
+    None
+}
+
+/// Returns the `src` attributes of all `<img>` tags in a html snippet, for instance an image that is dragged from
+/// another browser tab
+fn img_sources(html: &str) -> Vec<String> {
+    let lower = html.to_ascii_lowercase();
+    let mut sources = Vec::new();
+    let mut from = 0;
+    while let Some(start) = lower[from..].find("<img") {
+        let start = from + start;
+        let end = lower[start..]
+            .find('>')
+            .map_or(lower.len(), |end| start + end);
+        if let Some(src) = attribute(&html[start..end], &lower[start..end], "src") {
+            sources.push(src);
+        }
+        from = end;
+    }
+    sources
+}
+
+/// Checks a dropped url: remote urls are accepted as they are, they fail to load if they don't refer to an image, see
+/// `ImageStatus::Broken`. Data uris are checked like files.
+fn check_url(url: &str) -> Result<String, ImportError> {
+    let lower = url.to_ascii_lowercase();
+    if is_remote(url) {
+        return Ok(url.to_string());
+    }
+    if let Some(data) = lower.strip_prefix("data:") {
+        let media_type = data.split([';', ',']).next().unwrap_or_default();
+        let name = "dropped data".to_string();
+        if !ALLOWED_TYPES.contains(&media_type) {
+            return Err(ImportError::UnsupportedType(name, media_type.to_string()));
+        }
+        // base64 encodes 3 bytes in 4 characters
+        let size = url.len() as f64 * 0.75;
+        if size > MAX_FILE_SIZE {
+            return Err(ImportError::TooLarge(name, size));
+        }
+        return Ok(url.to_string());
+    }
+    Err(ImportError::UnsupportedUrl(url.to_string()))
+}
+
+/// Imports dropped text: the images of a html snippet or else the urls of a uri list or plain text, one url per line.
+/// Lines that start with a `#` are comments in a uri list. Returns the images that can be added and the errors of the
+/// urls that can't be imported.
+pub fn import_dropped_content(html: &str, text: &str) -> (Vec<ImportedImage>, Vec<ImportError>) {
+    let mut candidates = img_sources(html);
+    if candidates.is_empty() {
+        candidates = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect();
+    }
+    if candidates.is_empty() {
+        return (Vec::new(), vec![ImportError::NoImage]);
+    }
+
+    let mut images: Vec<ImportedImage> = Vec::new();
+    let mut errors = Vec::new();
+    for candidate in candidates {
+        match check_url(&candidate) {
+            Ok(url) if images.iter().any(|image| image.url == url) => (),
+            Ok(url) => images.push(ImportedImage {
+                is_vector: is_vector_url(&url),
+                url,
+                ..Default::default()
+            }),
+            Err(e) => errors.push(e),
+        }
+    }
+    (images, errors)
+}
@@ -5,21 +5,69 @@
 use crate::components::app::App;
 
 mod absolute_style;
+mod animation;
 pub mod components {
     pub mod app;
+    mod artboard_frame;
+    mod artboard_switch;
+    mod autosave;
+    mod board_switch;
+    mod broken_image;
     mod drag_and_drop;
+    mod export_buttons;
     mod file_dialog;
+    mod guides;
     pub mod handle;
     mod header;
     mod image_container;
+    mod image_toolbar;
     mod images;
+    mod inspector;
     mod logger;
+    mod marquee;
+    mod placement_switch;
+    mod property_input;
+    mod renderer_switch;
+    mod reset_button;
+    mod rulers;
     mod scalable_image;
+    mod scene;
+    mod spinner;
+    mod toasts;
 }
 mod app_state;
+mod artboard;
+mod artboard_preset;
+mod autosave;
+mod autosave_status;
+mod board;
+mod boards;
 mod bounding_box;
+mod draw;
+mod exif;
+mod export;
+mod frame_cache;
+mod guide;
 mod handle_id;
+mod hit_test;
+mod image_cache;
+mod image_property;
+mod image_status;
+mod import;
+mod object_urls;
+mod orientation;
+mod page_format;
+mod pattern_repeat;
+mod pdf_export;
+mod pixel_ratio;
+mod placement_policy;
 mod position;
+mod renderer;
+mod resize_mode;
+mod saved_scene;
+mod snap;
+mod svg_export;
+mod toast;
 
 fn main() {
     yew::Renderer::<App>::new().render();
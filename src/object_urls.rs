@@ -0,0 +1,143 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
+use wasm_bindgen::JsValue;
+use web_sys::{Blob, Url};
+
+/// The object urls created by the app:
+///
+/// - `owners`&rarr; For every url the ids of the images that use the url
+/// - `sources`&rarr; For every source the url that has been created for it, so that a file that is imported again
+/// shares the url with the images that use the file already
+#[derive(Default)]
+struct Registry {
+    owners: HashMap<String, HashSet<String>>,
+    sources: HashMap<String, String>,
+}
+
+impl Registry {
+    /// returns the url that is tracked for the source
+    fn url_of(&self, source: &str) -> Option<String> {
+        self.sources.get(source).cloned()
+    }
+
+    /// starts tracking a url that no image uses yet
+    fn track(&mut self, url: &str, source: &str) {
+        self.owners.insert(url.to_string(), HashSet::new());
+        self.sources.insert(source.to_string(), url.to_string());
+    }
+
+    /// registers an image as user of a tracked url
+    fn retain(&mut self, url: &str, image_id: &str) {
+        if let Some(image_ids) = self.owners.get_mut(url) {
+            image_ids.insert(image_id.to_string());
+        }
+    }
+
+    /// unregisters an image as user of the url, returns true if the url is no longer used and has to be revoked
+    fn release(&mut self, url: &str, image_id: &str) -> bool {
+        if let Some(image_ids) = self.owners.get_mut(url) {
+            image_ids.remove(image_id);
+        }
+        self.remove_unused(url)
+    }
+
+    /// stops tracking the url if no image uses it, returns true if the url has to be revoked
+    fn remove_unused(&mut self, url: &str) -> bool {
+        let is_unused = self
+            .owners
+            .get(url)
+            .is_some_and(|image_ids| image_ids.is_empty());
+        if is_unused {
+            self.owners.remove(url);
+            self.sources.retain(|_, source_url| source_url != url);
+        }
+        is_unused
+    }
+}
+
+thread_local! {
+    static REGISTRY: RefCell<Registry> = RefCell::new(Registry::default());
+}
+
+/// Creates an object url for a blob and starts tracking the url. The url is revoked as soon as the last image
+/// that uses the url is removed. The `source` identifies the content of the blob; as long as a url for the same source
+/// is tracked, that url is returned instead of creating another one.
+pub fn create(blob: &Blob, source: &str) -> Result<String, JsValue> {
+    if let Some(url) = REGISTRY.with(|registry| registry.borrow().url_of(source)) {
+        return Ok(url);
+    }
+    let url = Url::create_object_url_with_blob(blob)?;
+    REGISTRY.with(|registry| registry.borrow_mut().track(&url, source));
+    Ok(url)
+}
+
+/// Registers an image as user of the url. Urls that haven't been created by `create` are ignored.
+pub fn retain(url: &str, image_id: &str) {
+    REGISTRY.with(|registry| registry.borrow_mut().retain(url, image_id));
+}
+
+/// Unregisters an image as user of the url and revokes the url if no other image uses the url. This releases the
+/// blob that the url refers to.
+pub fn release(url: &str, image_id: &str) {
+    if REGISTRY.with(|registry| registry.borrow_mut().release(url, image_id)) {
+        let _ = Url::revoke_object_url(url);
+    }
+}
+
+/// Revokes a url that no image uses. Urls are created before the image that uses them is added to the scene, a url
+/// whose image is never added would otherwise stay alive until the page is closed.
+pub fn revoke_unused(url: &str) {
+    if REGISTRY.with(|registry| registry.borrow_mut().remove_unused(url)) {
+        let _ = Url::revoke_object_url(url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_url_is_revoked_after_its_last_owner_is_released() {
+        let mut registry = Registry::default();
+        registry.track("blob:1", "photo.jpg");
+        registry.retain("blob:1", "0");
+        registry.retain("blob:1", "1");
+        assert!(!registry.release("blob:1", "0"));
+        // releasing an image that doesn't use the url changes nothing
+        assert!(!registry.release("blob:1", "7"));
+        assert!(registry.release("blob:1", "1"));
+        // the url is no longer tracked, so it is revoked only once
+        assert!(!registry.release("blob:1", "1"));
+        assert!(registry.url_of("photo.jpg").is_none());
+    }
+
+    #[test]
+    fn the_same_source_shares_the_url() {
+        let mut registry = Registry::default();
+        registry.track("blob:1", "photo.jpg");
+        registry.retain("blob:1", "0");
+        assert_eq!(registry.url_of("photo.jpg").as_deref(), Some("blob:1"));
+        assert_eq!(registry.url_of("other.jpg"), None);
+        registry.retain("blob:1", "1");
+        assert!(!registry.release("blob:1", "0"));
+        assert!(registry.release("blob:1", "1"));
+    }
+
+    #[test]
+    fn only_unused_urls_are_revoked() {
+        let mut registry = Registry::default();
+        registry.track("blob:1", "a.png");
+        registry.track("blob:2", "b.png");
+        registry.retain("blob:2", "0");
+        assert!(registry.remove_unused("blob:1"));
+        assert!(!registry.remove_unused("blob:1"));
+        assert!(!registry.remove_unused("blob:2"));
+        // urls that haven't been created by the app are never revoked
+        registry.retain("https://example.com/a.png", "1");
+        assert!(!registry.release("https://example.com/a.png", "1"));
+        assert!(!registry.remove_unused("https://example.com/a.png"));
+    }
+}
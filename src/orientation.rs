@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+/// This enum contains the 8 orientations that can be stored in the EXIF data of a photo. The orientation describes how
+/// the stored pixels have to be transformed to show the photo upright.
+///
+/// Implements the following functions:
+/// - `from_tag`&rarr; converts the value of the EXIF orientation tag, unknown values are treated as `Normal`
+/// - `swaps_axes`&rarr; returns true if the width and the height of the photo are swapped when it is shown upright
+/// - `transform`&rarr; returns the canvas transform that draws the stored pixels upright
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Orientation {
+    #[default]
+    Normal,
+    FlipHorizontal,
+    Rotate180,
+    FlipVertical,
+    Transpose,
+    Rotate90,
+    Transverse,
+    Rotate270,
+}
+
+impl Orientation {
+    /// converts the value of the EXIF orientation tag
+    pub fn from_tag(value: u16) -> Orientation {
+        match value {
+            2 => Self::FlipHorizontal,
+            3 => Self::Rotate180,
+            4 => Self::FlipVertical,
+            5 => Self::Transpose,
+            6 => Self::Rotate90,
+            7 => Self::Transverse,
+            8 => Self::Rotate270,
+            _ => Self::Normal,
+        }
+    }
+
+    /// returns true if the photo is rotated a quarter turn
+    pub fn swaps_axes(&self) -> bool {
+        matches!(
+            self,
+            Self::Transpose | Self::Rotate90 | Self::Transverse | Self::Rotate270
+        )
+    }
+
+    /// Returns the arguments `a` to `f` of the canvas `transform` function that draws the stored pixels, which are
+    /// `width` by `height` pixels, upright at the origin.
+    pub fn transform(&self, width: f64, height: f64) -> [f64; 6] {
+        match self {
+            Self::Normal => [1., 0., 0., 1., 0., 0.],
+            Self::FlipHorizontal => [-1., 0., 0., 1., width, 0.],
+            Self::Rotate180 => [-1., 0., 0., -1., width, height],
+            Self::FlipVertical => [1., 0., 0., -1., 0., height],
+            Self::Transpose => [0., 1., 1., 0., 0., 0.],
+            Self::Rotate90 => [0., 1., -1., 0., height, 0.],
+            Self::Transverse => [0., -1., -1., 0., height, width],
+            Self::Rotate270 => [0., -1., 1., 0., 0., width],
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::fmt;
+
+/// The width and the height of the paper formats in points, in portrait orientation
+const A4: (f64, f64) = (595.28, 841.89);
+const LETTER: (f64, f64) = (612., 792.);
+
+/// This enum contains the page formats of the pdf export.
+///
+/// - `SceneBounds`&rarr; the page has the size of the area that is covered by the images, at 96 pixels per inch
+/// - `A4`, `Letter`&rarr; the images are scaled down to fit on the paper, if needed, and centered on the page
+///
+/// Implements the following functions:
+/// - `to_string`&rarr; prints out a label that can be used in the user interface
+/// - `next`&rarr; returns the next format, used to cycle through the formats
+/// - `paper_size`&rarr; returns the size of the paper in points
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum PageFormat {
+    #[default]
+    SceneBounds,
+    A4,
+    Letter,
+}
+
+/// prints out a label that can be used in the user interface
+impl fmt::Display for PageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = match &self {
+            Self::SceneBounds => "scene",
+            Self::A4 => "A4",
+            Self::Letter => "letter",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl PageFormat {
+    /// returns the next format
+    pub fn next(&self) -> PageFormat {
+        match &self {
+            Self::SceneBounds => Self::A4,
+            Self::A4 => Self::Letter,
+            Self::Letter => Self::SceneBounds,
+        }
+    }
+
+    /// Returns the width and the height of the paper in points, in landscape orientation if `landscape` is set.
+    /// Returns `None` for `SceneBounds`, the size of that page follows from the images.
+    pub fn paper_size(&self, landscape: bool) -> Option<(f64, f64)> {
+        let (width, height) = match &self {
+            Self::SceneBounds => return None,
+            Self::A4 => A4,
+            Self::Letter => LETTER,
+        };
+        Some(if landscape {
+            (height, width)
+        } else {
+            (width, height)
+        })
+    }
+}
@@ -0,0 +1,43 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// This enum contains the repeat modes of a pattern, they map one on one to the repetition values of
+/// the canvas `createPattern` function.
+///
+/// Implements the following functions:
+/// - `to_string`&rarr; prints out the repetition value that can be passed to `createPattern`
+/// - `next`&rarr; returns the next repeat mode, used to cycle through all modes with a single key
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PatternRepeat {
+    #[default]
+    Repeat,
+    RepeatX,
+    RepeatY,
+    NoRepeat,
+}
+
+/// prints out the repetition value that can be passed to `createPattern`
+impl fmt::Display for PatternRepeat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let repetition = match &self {
+            Self::Repeat => "repeat",
+            Self::RepeatX => "repeat-x",
+            Self::RepeatY => "repeat-y",
+            Self::NoRepeat => "no-repeat",
+        };
+        write!(f, "{}", repetition)
+    }
+}
+
+impl PatternRepeat {
+    /// returns the next repeat mode, after `NoRepeat` it starts over with `Repeat`
+    pub fn next(&self) -> PatternRepeat {
+        match &self {
+            Self::Repeat => Self::RepeatX,
+            Self::RepeatX => Self::RepeatY,
+            Self::RepeatY => Self::NoRepeat,
+            Self::NoRepeat => Self::Repeat,
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use std::fmt::Write;
+
+use miniz_oxide::deflate::compress_to_vec_zlib;
+
+use crate::{artboard::Artboard, bounding_box::BoundingBox, page_format::PageFormat};
+
+/// Points per css pixel: css pixels are 1/96 inch, points are 1/72 inch
+const POINTS_PER_PIXEL: f64 = 0.75;
+/// The margin around the images on paper, in points
+const MARGIN: f64 = 36.;
+/// The compression level of the image data and the page content, from 0 to 10
+const COMPRESSION_LEVEL: u8 = 6;
+
+/// The pixels of an image in the pdf:
+///
+/// - `Jpeg`&rarr; The JPEG file itself, which pdf readers decode themselves, with the number of colour components
+/// - `Rgba`&rarr; Uncompressed pixels, 4 bytes per pixel, used for every image that is rasterized by the app; the alpha
+/// channel becomes a soft mask, unless the image is opaque
+pub enum PdfImageData {
+    Jpeg(Vec<u8>, u8),
+    Rgba(Vec<u8>),
+}
+
+/// An image that is placed on the page, with its position and size in the scene and the size of its pixel data
+pub struct PdfImage {
+    pub bounding_box: BoundingBox,
+    pub pixel_width: u32,
+    pub pixel_height: u32,
+    pub data: PdfImageData,
+}
+
+/// Returns the width, the height and the number of colour components of a JPEG file, read from the start of frame
+/// segment. Returns `None` if the data is not a JPEG file.
+pub fn jpeg_info(bytes: &[u8]) -> Option<(u32, u32, u8)> {
+    if bytes.get(0..2)? != [0xff, 0xd8] {
+        return None;
+    }
+    let mut offset = 2;
+    while let [0xff, marker, high, low, ..] = bytes.get(offset..)? {
+        // every start of frame marker except the markers that share the range: DHT, JPG and DAC
+        if (0xc0..=0xcf).contains(marker) && ![0xc4, 0xc8, 0xcc].contains(marker) {
+            let segment = bytes.get(offset + 4..offset + 10)?;
+            let height = u16::from_be_bytes([segment[1], segment[2]]) as u32;
+            let width = u16::from_be_bytes([segment[3], segment[4]]) as u32;
+            return Some((width, height, segment[5]));
+        }
+        if *marker == 0xda || *marker == 0xd9 {
+            return None;
+        }
+        offset += 2 + u16::from_be_bytes([*high, *low]) as usize;
+    }
+    None
+}
+
+/// Returns true if every pixel of rgba data is fully opaque, these images don't need a soft mask
+fn is_opaque(pixels: &[u8]) -> bool {
+    pixels.chunks_exact(4).all(|pixel| pixel[3] == 255)
+}
+
+/// Writes the objects of a pdf file and keeps track of their offsets for the cross reference table. Objects are
+/// numbered from 1 in the order they are written.
+struct PdfWriter {
+    bytes: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl PdfWriter {
+    fn new() -> PdfWriter {
+        let mut bytes = b"%PDF-1.4\n".to_vec();
+        // a comment with binary characters tells readers the file contains binary data
+        bytes.extend_from_slice(b"%\xe2\xe3\xcf\xd3\n");
+        PdfWriter {
+            bytes,
+            offsets: Vec::new(),
+        }
+    }
+
+    fn object(&mut self, dictionary: &str) {
+        self.offsets.push(self.bytes.len());
+        let number = self.offsets.len();
+        let _ = write!(self, "{} 0 obj\n{}\nendobj\n", number, dictionary);
+    }
+
+    fn stream(&mut self, dictionary: &str, data: &[u8]) {
+        self.offsets.push(self.bytes.len());
+        let number = self.offsets.len();
+        let _ = write!(
+            self,
+            "{} 0 obj\n<< {} /Length {} >>\nstream\n",
+            number,
+            dictionary,
+            data.len()
+        );
+        self.bytes.extend_from_slice(data);
+        self.bytes.extend_from_slice(b"\nendstream\nendobj\n");
+    }
+
+    /// Writes the cross reference table and the trailer, the first object is the catalog
+    fn finish(mut self) -> Vec<u8> {
+        let xref = self.bytes.len();
+        let count = self.offsets.len() + 1;
+        let _ = write!(self, "xref\n0 {}\n0000000000 65535 f \n", count);
+        for offset in self.offsets.clone() {
+            let _ = writeln!(self, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            self,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            count, xref
+        );
+        self.bytes
+    }
+}
+
+impl Write for PdfWriter {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.bytes.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Returns the size of the page, the scale from css pixels to points and the position of the top left corner of the
+/// images on the page. On paper the images are scaled down if they don't fit within the margins, and centered.
+fn page_layout(format: PageFormat, bounds: &BoundingBox) -> ((f64, f64), f64, (f64, f64)) {
+    let (width, height) = (bounds.width as f64, bounds.height as f64);
+    match format.paper_size(width > height) {
+        None => (
+            (width * POINTS_PER_PIXEL, height * POINTS_PER_PIXEL),
+            POINTS_PER_PIXEL,
+            (0., 0.),
+        ),
+        Some((page_width, page_height)) => {
+            let scale = ((page_width - 2. * MARGIN) / width)
+                .min((page_height - 2. * MARGIN) / height)
+                .min(POINTS_PER_PIXEL);
+            let left = (page_width - width * scale) / 2.;
+            let top = (page_height - height * scale) / 2.;
+            ((page_width, page_height), scale, (left, top))
+        }
+    }
+}
+
+/// Generates a pdf file with a single page that shows the images at their position and size; the images are drawn in
+/// the given order, so the last image is on top. The page is sized to the area covered by the images or has the
+/// chosen paper format, see `PageFormat`. If there is an artboard, the artboard takes the place of the area covered by
+/// the images: the images are clipped to the artboard, which is filled with its background colour first.
+///
+/// Returns `None` if there are no images.
+pub fn to_pdf(images: &[PdfImage], format: PageFormat, artboard: &Artboard) -> Option<Vec<u8>> {
+    let bounds = images
+        .iter()
+        .map(|image| image.bounding_box)
+        .reduce(|a, b| a.union(&b))?;
+    let bounds = artboard.bounding_box().unwrap_or(bounds);
+    let ((page_width, page_height), scale, (left, top)) = page_layout(format, &bounds);
+
+    // pdf coordinates start at the bottom left corner of the page
+    let mut content = String::new();
+    if artboard.bounding_box().is_some() {
+        let (width, height) = (bounds.width as f64 * scale, bounds.height as f64 * scale);
+        let bottom = page_height - top - height;
+        if let Some((r, g, b)) = artboard.background_rgb() {
+            let _ = writeln!(
+                content,
+                "{:.3} {:.3} {:.3} rg {:.3} {:.3} {:.3} {:.3} re f",
+                r as f64 / 255.,
+                g as f64 / 255.,
+                b as f64 / 255.,
+                left,
+                bottom,
+                width,
+                height
+            );
+        }
+        // the clipping path stays in effect for the rest of the page
+        let _ = writeln!(
+            content,
+            "{:.3} {:.3} {:.3} {:.3} re W n",
+            left, bottom, width, height
+        );
+    }
+    for (i, image) in images.iter().enumerate() {
+        let bb = &image.bounding_box;
+        let x = left + (bb.x as f64 - bounds.x as f64) * scale;
+        let y = page_height - top - (bb.y as f64 - bounds.y as f64 + bb.height as f64) * scale;
+        let _ = writeln!(
+            content,
+            "q {:.3} 0 0 {:.3} {:.3} {:.3} cm /Im{} Do Q",
+            bb.width as f64 * scale,
+            bb.height as f64 * scale,
+            x,
+            y,
+            i
+        );
+    }
+
+    // the catalog, the page tree, the page and its content come first, the images are numbered from 5; the soft mask
+    // of an image directly follows the image
+    let mut numbers = Vec::with_capacity(images.len());
+    let mut next_number = 5;
+    for image in images {
+        numbers.push(next_number);
+        next_number += match &image.data {
+            PdfImageData::Rgba(pixels) if !is_opaque(pixels) => 2,
+            _ => 1,
+        };
+    }
+
+    let mut pdf = PdfWriter::new();
+    pdf.object("<< /Type /Catalog /Pages 2 0 R >>");
+    pdf.object("<< /Type /Pages /Kids [3 0 R] /Count 1 >>");
+    let x_objects = numbers
+        .iter()
+        .enumerate()
+        .map(|(i, number)| format!("/Im{} {} 0 R", i, number))
+        .collect::<Vec<String>>()
+        .join(" ");
+    pdf.object(&format!(
+        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /XObject << {} >> >> /Contents 4 0 R >>",
+        page_width, page_height, x_objects
+    ));
+    pdf.stream(
+        "/Filter /FlateDecode",
+        &compress_to_vec_zlib(content.as_bytes(), COMPRESSION_LEVEL),
+    );
+    for (image, number) in images.iter().zip(numbers) {
+        let size = format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} /BitsPerComponent 8",
+            image.pixel_width, image.pixel_height
+        );
+        match &image.data {
+            PdfImageData::Jpeg(bytes, components) => {
+                let color_space = if *components == 1 {
+                    "/DeviceGray"
+                } else {
+                    "/DeviceRGB"
+                };
+                pdf.stream(
+                    &format!("{} /ColorSpace {} /Filter /DCTDecode", size, color_space),
+                    bytes,
+                );
+            }
+            PdfImageData::Rgba(pixels) => {
+                let rgb: Vec<u8> = pixels
+                    .chunks_exact(4)
+                    .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                    .collect();
+                let soft_mask = if is_opaque(pixels) {
+                    String::new()
+                } else {
+                    format!(" /SMask {} 0 R", number + 1)
+                };
+                pdf.stream(
+                    &format!(
+                        "{} /ColorSpace /DeviceRGB{} /Filter /FlateDecode",
+                        size, soft_mask
+                    ),
+                    &compress_to_vec_zlib(&rgb, COMPRESSION_LEVEL),
+                );
+                if !soft_mask.is_empty() {
+                    let alpha: Vec<u8> = pixels.chunks_exact(4).map(|pixel| pixel[3]).collect();
+                    pdf.stream(
+                        &format!("{} /ColorSpace /DeviceGray /Filter /FlateDecode", size),
+                        &compress_to_vec_zlib(&alpha, COMPRESSION_LEVEL),
+                    );
+                }
+            }
+        }
+    }
+    Some(pdf.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba_image(x: i16, alpha: u8) -> PdfImage {
+        PdfImage {
+            bounding_box: BoundingBox {
+                x,
+                y: 0,
+                width: 2,
+                height: 1,
+            },
+            pixel_width: 2,
+            pixel_height: 1,
+            data: PdfImageData::Rgba(vec![10, 20, 30, 255, 40, 50, 60, alpha]),
+        }
+    }
+
+    /// Returns the position of the first occurrence of the text in the pdf
+    fn find(pdf: &[u8], text: &str) -> Option<usize> {
+        pdf.windows(text.len())
+            .position(|window| window == text.as_bytes())
+    }
+
+    fn count(pdf: &[u8], text: &str) -> usize {
+        pdf.windows(text.len())
+            .filter(|window| *window == text.as_bytes())
+            .count()
+    }
+
+    #[test]
+    fn jpeg_info_skips_the_segments_before_the_start_of_frame() {
+        let jpeg = [
+            &[0xff, 0xd8][..],
+            // APP0 and DHT segments, the DHT marker is in the range of the start of frame markers
+            &[0xff, 0xe0, 0x00, 0x04, 0x4a, 0x46],
+            &[0xff, 0xc4, 0x00, 0x05, 0x00, 0x01, 0x02],
+            // baseline start of frame: precision, height 480, width 640, 3 components
+            &[0xff, 0xc0, 0x00, 0x11, 0x08, 0x01, 0xe0, 0x02, 0x80, 0x03],
+        ]
+        .concat();
+        assert_eq!(jpeg_info(&jpeg), Some((640, 480, 3)));
+        // a progressive grayscale photo
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xc2, 0x00, 0x0b, 0x08, 0x00, 0x10, 0x00, 0x20, 0x01,
+        ];
+        assert_eq!(jpeg_info(&jpeg), Some((32, 16, 1)));
+    }
+
+    #[test]
+    fn jpeg_info_rejects_other_data() {
+        assert_eq!(jpeg_info(b"\x89PNG\r\n\x1a\n"), None);
+        assert_eq!(jpeg_info(&[0xff, 0xd8]), None);
+        // the image data starts before a start of frame segment
+        assert_eq!(jpeg_info(&[0xff, 0xd8, 0xff, 0xda, 0x00, 0x02]), None);
+        // the start of frame segment is cut off
+        assert_eq!(jpeg_info(&[0xff, 0xd8, 0xff, 0xc0, 0x00, 0x11, 0x08]), None);
+    }
+
+    #[test]
+    fn the_cross_reference_table_points_at_every_object() {
+        let images = [rgba_image(0, 255), rgba_image(2, 0)];
+        let pdf = to_pdf(&images, PageFormat::SceneBounds, &Artboard::default()).unwrap();
+        let start = find(&pdf, "startxref\n").unwrap() + "startxref\n".len();
+        let xref: usize = String::from_utf8_lossy(&pdf[start..])
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let table = String::from_utf8_lossy(&pdf[xref..]).to_string();
+        let mut lines = table.lines();
+        assert_eq!(lines.next(), Some("xref"));
+        // the catalog, the page tree, the page, the content, two images and one soft mask
+        assert_eq!(lines.next(), Some("0 8"));
+        assert_eq!(lines.next(), Some("0000000000 65535 f "));
+        for number in 1..8 {
+            let offset: usize = lines.next().unwrap()[..10].parse().unwrap();
+            let object = format!("{} 0 obj\n", number);
+            assert!(pdf[offset..].starts_with(object.as_bytes()));
+        }
+        assert!(table.contains("trailer\n<< /Size 8 /Root 1 0 R >>"));
+    }
+
+    #[test]
+    fn only_transparent_images_get_a_soft_mask() {
+        let pdf = to_pdf(&[rgba_image(0, 255)], PageFormat::A4, &Artboard::default()).unwrap();
+        assert_eq!(count(&pdf, "/SMask"), 0);
+        let images = [rgba_image(0, 255), rgba_image(2, 128)];
+        let pdf = to_pdf(&images, PageFormat::A4, &Artboard::default()).unwrap();
+        // the opaque image is object 5, the transparent image is object 6 and its mask directly follows it
+        assert_eq!(count(&pdf, "/SMask"), 1);
+        assert!(find(&pdf, "6 0 obj\n<< /Type /XObject").is_some());
+        assert!(find(&pdf, "/SMask 7 0 R").is_some());
+        assert!(find(&pdf, "7 0 obj\n<< /Type /XObject /Subtype /Image /Width 2 /Height 1 /BitsPerComponent 8 /ColorSpace /DeviceGray").is_some());
+    }
+
+    #[test]
+    fn a_large_scene_is_scaled_down_to_fit_on_the_paper() {
+        let bounds = BoundingBox {
+            x: -500,
+            y: 0,
+            width: 2000,
+            height: 1000,
+        };
+        let ((width, height), scale, (left, top)) = page_layout(PageFormat::A4, &bounds);
+        // a wide scene is printed in landscape orientation
+        assert_eq!((width, height), (841.89, 595.28));
+        assert!(scale < POINTS_PER_PIXEL);
+        assert!(left >= MARGIN && top >= MARGIN);
+        assert!(2000. * scale <= width - 2. * MARGIN + 1e-9);
+        assert!(1000. * scale <= height - 2. * MARGIN + 1e-9);
+        // the scene is centered on the page
+        assert!((left - (width - 2000. * scale - left)).abs() < 1e-9);
+        assert!((top - (height - 1000. * scale - top)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_small_scene_is_not_scaled_up() {
+        let bounds = BoundingBox {
+            x: 0,
+            y: 0,
+            width: 200,
+            height: 400,
+        };
+        let ((width, height), scale, _) = page_layout(PageFormat::A4, &bounds);
+        assert_eq!((width, height), (595.28, 841.89));
+        assert_eq!(scale, POINTS_PER_PIXEL);
+        let (page, scale, origin) = page_layout(PageFormat::SceneBounds, &bounds);
+        assert_eq!(page, (150., 300.));
+        assert_eq!(scale, POINTS_PER_PIXEL);
+        assert_eq!(origin, (0., 0.));
+    }
+}
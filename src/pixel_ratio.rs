@@ -0,0 +1,40 @@
+/// The number of device pixels per css pixel of the display the app is shown on. The geometry of the images is
+/// stored in css pixels, only the backing stores of the canvases are scaled by this ratio so that images are drawn
+/// sharply on high resolution displays.
+///
+/// The ratio changes when the user zooms the page or moves the window to another display.
+///
+/// Implements the following functions:
+/// - `current`&rarr; returns the ratio of the display the window is shown on
+/// - `scale`&rarr; converts a length in css pixels to device pixels
+/// - `media_query`&rarr; returns a media query that stops matching as soon as the ratio changes
+#[derive(Clone, Copy, PartialEq)]
+pub struct PixelRatio(pub f64);
+
+impl Default for PixelRatio {
+    fn default() -> Self {
+        PixelRatio(1.0)
+    }
+}
+
+impl PixelRatio {
+    /// returns the ratio of the display the window is shown on
+    pub fn current() -> PixelRatio {
+        let ratio = web_sys::window().unwrap().device_pixel_ratio();
+        if ratio > 0.0 {
+            PixelRatio(ratio)
+        } else {
+            PixelRatio::default()
+        }
+    }
+
+    /// converts a length in css pixels to device pixels, the result is at least 1 pixel
+    pub fn scale(&self, length: i16) -> u32 {
+        (length as f64 * self.0).round().max(1.0) as u32
+    }
+
+    /// returns a media query that stops matching as soon as the ratio changes
+    pub fn media_query(&self) -> String {
+        format!("(resolution: {}dppx)", self.0)
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// The size that is used for images without natural size, for instance svg images without width and height; this is the
+/// default size of replaced elements in the browser
+const FALLBACK_SIZE: (i16, i16) = (300, 150);
+/// The width or height that the policies with a fixed size start with
+const DEFAULT_SIZE: i16 = 300;
+/// The percentage of the viewport that the fit policy starts with
+const DEFAULT_PERCENTAGE: i16 = 50;
+/// The largest fixed width or height
+pub const MAX_SIZE: i16 = 10_000;
+
+/// This enum contains the policies that determine the size of an image as soon as it has loaded.
+///
+/// - `NaturalSize`&rarr; the image is placed at its natural size, which works best for pixel art and icons
+/// - `FitViewport`&rarr; the image is scaled down so that it fits in the given fraction of the viewport, small images keep
+/// their natural size
+/// - `FixedWidth`&rarr; the image gets the given width, the height follows from the natural ratio of the image
+/// - `FixedHeight`&rarr; the image gets the given height, the width follows from the natural ratio of the image
+///
+/// Implements the following functions:
+/// - `to_string`&rarr; prints out a label that can be used in the user interface
+/// - `next`&rarr; returns the next policy, used to cycle through the policies
+/// - `value`&rarr; returns the percentage or the size that can be set by the user, if the policy has one
+/// - `with_value`&rarr; returns the policy with another percentage or size
+/// - `value_range`&rarr; returns the smallest and the largest value of the policy
+/// - `initial_size`&rarr; calculates the width and the height of a freshly loaded image
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PlacementPolicy {
+    NaturalSize,
+    FitViewport(f32),
+    FixedWidth(i16),
+    FixedHeight(i16),
+}
+
+impl Default for PlacementPolicy {
+    fn default() -> Self {
+        Self::FixedWidth(DEFAULT_SIZE)
+    }
+}
+
+/// prints out a label that can be used in the user interface
+impl fmt::Display for PlacementPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            Self::NaturalSize => write!(f, "natural size"),
+            Self::FitViewport(fraction) => write!(f, "fit {}%", (fraction * 100.0).round()),
+            Self::FixedWidth(width) => write!(f, "width {}px", width),
+            Self::FixedHeight(height) => write!(f, "height {}px", height),
+        }
+    }
+}
+
+impl PlacementPolicy {
+    /// returns the next policy
+    pub fn next(&self) -> PlacementPolicy {
+        match &self {
+            Self::NaturalSize => Self::FitViewport(DEFAULT_PERCENTAGE as f32 / 100.0),
+            Self::FitViewport(_) => Self::FixedWidth(DEFAULT_SIZE),
+            Self::FixedWidth(_) => Self::FixedHeight(DEFAULT_SIZE),
+            Self::FixedHeight(_) => Self::NaturalSize,
+        }
+    }
+
+    /// returns the percentage of the viewport or the fixed size, or `None` for the natural size
+    pub fn value(&self) -> Option<i16> {
+        match &self {
+            Self::NaturalSize => None,
+            Self::FitViewport(fraction) => Some((fraction * 100.0).round() as i16),
+            Self::FixedWidth(size) | Self::FixedHeight(size) => Some(*size),
+        }
+    }
+
+    /// returns the policy with another percentage or size, the value is kept within `value_range`
+    pub fn with_value(&self, value: i16) -> PlacementPolicy {
+        let (min, max) = self.value_range();
+        let value = value.clamp(min, max);
+        match &self {
+            Self::NaturalSize => Self::NaturalSize,
+            Self::FitViewport(_) => Self::FitViewport(value as f32 / 100.0),
+            Self::FixedWidth(_) => Self::FixedWidth(value),
+            Self::FixedHeight(_) => Self::FixedHeight(value),
+        }
+    }
+
+    /// returns the smallest and the largest value of the policy
+    pub fn value_range(&self) -> (i16, i16) {
+        match &self {
+            Self::FitViewport(_) => (1, 100),
+            _ => (1, MAX_SIZE),
+        }
+    }
+
+    /// Calculates the width and the height of an image from its natural size. The ratio of the image is always the
+    /// natural ratio, the returned size is at least 1 by 1 pixel.
+    pub fn initial_size(
+        &self,
+        natural_width: i16,
+        natural_height: i16,
+        (viewport_width, viewport_height): (i16, i16),
+    ) -> (i16, i16) {
+        let (natural_width, natural_height) = if natural_width > 0 && natural_height > 0 {
+            (natural_width as f32, natural_height as f32)
+        } else {
+            (FALLBACK_SIZE.0 as f32, FALLBACK_SIZE.1 as f32)
+        };
+        let scale = match &self {
+            Self::NaturalSize => 1.0,
+            Self::FitViewport(fraction) => {
+                if viewport_width == 0 || viewport_height == 0 {
+                    1.0
+                } else {
+                    let max_width = viewport_width as f32 * fraction;
+                    let max_height = viewport_height as f32 * fraction;
+                    (max_width / natural_width)
+                        .min(max_height / natural_height)
+                        .min(1.0)
+                }
+            }
+            Self::FixedWidth(width) => *width as f32 / natural_width,
+            Self::FixedHeight(height) => *height as f32 / natural_height,
+        };
+        let width = (natural_width * scale).round().clamp(1.0, i16::MAX as f32);
+        let height = (natural_height * scale).round().clamp(1.0, i16::MAX as f32);
+        (width as i16, height as i16)
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// This enum contains the modes in which the images can be rendered.
+///
+/// - `Dom`&rarr; every image is rendered in its own container with its own canvas and html resize handles
+/// - `Canvas`&rarr; the whole scene, including the resize handles, is drawn on a single canvas
+///
+/// Implements the following functions:
+/// - `to_string`&rarr; prints out a label that can be used in the user interface
+/// - `toggle`&rarr; returns the other mode
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Renderer {
+    #[default]
+    Dom,
+    Canvas,
+}
+
+/// prints out a label that can be used in the user interface
+impl fmt::Display for Renderer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = match &self {
+            Self::Dom => "dom",
+            Self::Canvas => "canvas",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl Renderer {
+    /// returns the other mode
+    pub fn toggle(&self) -> Renderer {
+        match &self {
+            Self::Dom => Self::Canvas,
+            Self::Canvas => Self::Dom,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// This enum contains the modes in which the resize handles of a pattern can operate.
+///
+/// - `Surface`&rarr; the handles resize the surface that displays the pattern, the size of the tile stays the same
+/// - `Tile`&rarr; the handles resize the tile that is repeated, the size of the surface stays the same
+///
+/// Implements the following functions:
+/// - `to_string`&rarr; prints out a label that can be used in the user interface
+/// - `toggle`&rarr; returns the other mode
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ResizeMode {
+    #[default]
+    Surface,
+    Tile,
+}
+
+/// prints out a label that can be used in the user interface
+impl fmt::Display for ResizeMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = match &self {
+            Self::Surface => "surface",
+            Self::Tile => "tile",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl ResizeMode {
+    /// returns the other mode
+    pub fn toggle(&self) -> ResizeMode {
+        match &self {
+            Self::Surface => Self::Tile,
+            Self::Tile => Self::Surface,
+        }
+    }
+}
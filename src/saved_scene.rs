@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{app_state::AppState, boards::Boards};
+
+/// The part of the state that is saved automatically and restored in the next session, see `autosave`. Everything
+/// else, like the selection and the position of the mouse, only matters during a session.
+///
+/// - `boards`&rarr; All boards with their images and settings, including the images and settings of the active board. The
+/// playback state of animations is left out, so that a playing animation doesn't keep the scene from being saved;
+/// restored animations play from the first frame
+/// - `next_image_id`&rarr; So that images that are added after restoring get a unique id
+#[derive(Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedScene {
+    pub boards: Boards,
+    pub next_image_id: usize,
+}
+
+impl SavedScene {
+    /// Copies the part of the state that is saved
+    pub fn new(state: &AppState) -> SavedScene {
+        let mut boards = state.boards.clone();
+        let board = &mut boards.list[state.boards.active];
+        board.images = state.images.clone();
+        board.next_z_index = state.next_z_index;
+        board.renderer = state.renderer;
+        board.placement_policy = state.placement_policy;
+        board.artboard = state.artboard.clone();
+        board.guides = state.guides.clone();
+        for img in boards
+            .list
+            .iter_mut()
+            .flat_map(|board| board.images.iter_mut())
+        {
+            img.decoded = false;
+            img.frame_delays.clear();
+            img.frame = 0;
+            img.playing = false;
+            img.frame_started = 0.;
+        }
+        SavedScene {
+            boards,
+            next_image_id: state.next_image_id,
+        }
+    }
+
+    /// Returns true if any board has images, a scene without images isn't worth restoring
+    pub fn has_images(&self) -> bool {
+        self.boards
+            .list
+            .iter()
+            .any(|board| !board.images.is_empty())
+    }
+}
@@ -0,0 +1,78 @@
+use std::cmp::Ordering;
+
+use crate::{bounding_box::BoundingBox, guide::Guide};
+
+/// The distance in pixels within which the edge of an image snaps to a guide
+pub const SNAP_DISTANCE: i16 = 6;
+
+/// Returns the x-coordinates of the vertical guides and the y-coordinates of the horizontal guides
+fn positions(guides: &[Guide]) -> (Vec<i16>, Vec<i16>) {
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    for guide in guides {
+        match guide {
+            Guide::Vertical(x) => xs.push(*x),
+            Guide::Horizontal(y) => ys.push(*y),
+        }
+    }
+    (xs, ys)
+}
+
+/// Returns the smallest offset that moves one of the edges onto one of the guides, if that offset is within
+/// `SNAP_DISTANCE`
+fn offset(edges: &[i16], guides: &[i16]) -> Option<i16> {
+    edges
+        .iter()
+        .flat_map(|edge| guides.iter().map(move |guide| guide - edge))
+        .filter(|offset| offset.abs() <= SNAP_DISTANCE)
+        .min_by_key(|offset| offset.abs())
+}
+
+/// Snaps an image that is dragged: the image is moved so that its left or right edge lies on a vertical guide and its
+/// top or bottom edge on a horizontal guide, if these are close enough. The size stays the same.
+pub fn snap_move(bb: BoundingBox, guides: &[Guide]) -> BoundingBox {
+    let (xs, ys) = positions(guides);
+    let dx = offset(&[bb.x, bb.x + bb.width], &xs).unwrap_or(0);
+    let dy = offset(&[bb.y, bb.y + bb.height], &ys).unwrap_or(0);
+    BoundingBox {
+        x: bb.x + dx,
+        y: bb.y + dy,
+        ..bb
+    }
+}
+
+/// Snaps an image that is resized: only the edges that the resize handle moves snap to the guides, the opposite edges
+/// stay in place. The direction of the handle is -1 for the left or top edge, 1 for the right or bottom edge and 0 if
+/// the handle doesn't move an edge along that axis, see `HandleId::edges`.
+pub fn snap_resize(bb: BoundingBox, (dir_x, dir_y): (i16, i16), guides: &[Guide]) -> BoundingBox {
+    let (xs, ys) = positions(guides);
+    let mut snapped = bb;
+    match dir_x.cmp(&0) {
+        Ordering::Less => {
+            let dx = offset(&[bb.x], &xs).unwrap_or(0);
+            snapped.x += dx;
+            snapped.width -= dx;
+        }
+        Ordering::Greater => snapped.width += offset(&[bb.x + bb.width], &xs).unwrap_or(0),
+        Ordering::Equal => (),
+    }
+    match dir_y.cmp(&0) {
+        Ordering::Less => {
+            let dy = offset(&[bb.y], &ys).unwrap_or(0);
+            snapped.y += dy;
+            snapped.height -= dy;
+        }
+        Ordering::Greater => snapped.height += offset(&[bb.y + bb.height], &ys).unwrap_or(0),
+        Ordering::Equal => (),
+    }
+    // an image never snaps to a size of zero
+    if snapped.width < 1 {
+        snapped.x = bb.x;
+        snapped.width = bb.width;
+    }
+    if snapped.height < 1 {
+        snapped.y = bb.y;
+        snapped.height = bb.height;
+    }
+    snapped
+}
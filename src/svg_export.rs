@@ -0,0 +1,329 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{
+    app_state::ImageData, artboard::Artboard, bounding_box::BoundingBox, orientation::Orientation,
+    pattern_repeat::PatternRepeat,
+};
+
+/// Escapes the characters that have a special meaning in an xml attribute value
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Returns the element that draws the source of an image into a box of `width` by `height` at the origin. Photos that
+/// the app turns upright are stored sideways, these get the same transform as on the canvas, see `image_cache`.
+fn image_element(img: &ImageData, href: &str, width: f64, height: f64) -> String {
+    let href = escape(href);
+    if img.orientation == Orientation::Normal {
+        return format!(
+            r#"<image xlink:href="{}" width="{}" height="{}" preserveAspectRatio="none"/>"#,
+            href, width, height
+        );
+    }
+    // the natural size is the size of the upright photo
+    let (upright_width, upright_height) = (img.natural_width as f64, img.natural_height as f64);
+    let (stored_width, stored_height) = if img.orientation.swaps_axes() {
+        (upright_height, upright_width)
+    } else {
+        (upright_width, upright_height)
+    };
+    let [a, b, c, d, e, f] = img.orientation.transform(stored_width, stored_height);
+    format!(
+        r#"<g transform="scale({} {}) matrix({} {} {} {} {} {})"><image xlink:href="{}" width="{}" height="{}" preserveAspectRatio="none"/></g>"#,
+        width / upright_width,
+        height / upright_height,
+        a,
+        b,
+        c,
+        d,
+        e,
+        f,
+        href,
+        stored_width,
+        stored_height
+    )
+}
+
+/// Returns the `<pattern>` element and the rectangle that is filled with the pattern.
+///
+/// Svg patterns always repeat in both directions. For the directions in which the tile isn't repeated, the pattern is
+/// made so large that only the first tile falls within the surface: the distance from the origin of the pattern to
+/// any point of the surface is at most `extent`, whatever the rotation.
+fn pattern_elements(img: &ImageData, href: &str) -> String {
+    let (tile_width, tile_height) = (img.pattern_width as f64, img.pattern_height as f64);
+    let (period_x, period_y) = if img.pattern_mirror {
+        (2. * tile_width, 2. * tile_height)
+    } else {
+        (tile_width, tile_height)
+    };
+    let extent = (img.width.abs() as f64)
+        + (img.height.abs() as f64)
+        + (img.pattern_x.abs() as f64)
+        + (img.pattern_y.abs() as f64);
+    let (repeat_x, repeat_y) = match img.pattern_repeat {
+        PatternRepeat::Repeat => (true, true),
+        PatternRepeat::RepeatX => (true, false),
+        PatternRepeat::RepeatY => (false, true),
+        PatternRepeat::NoRepeat => (false, false),
+    };
+    let pattern_width = if repeat_x {
+        period_x
+    } else {
+        period_x + extent
+    };
+    let pattern_height = if repeat_y {
+        period_y
+    } else {
+        period_y + extent
+    };
+
+    // mirrored patterns repeat a tile that holds the image 4 times, as is and flipped, see `draw::create_pattern`
+    let flips: &[(bool, bool)] = if img.pattern_mirror {
+        &[(false, false), (true, false), (false, true), (true, true)]
+    } else {
+        &[(false, false)]
+    };
+    let tiles = flips
+        .iter()
+        .fold(String::new(), |mut tiles, (flip_x, flip_y)| {
+            let _ = write!(
+                tiles,
+                r#"<g transform="translate({} {}) scale({} {})">{}</g>"#,
+                if *flip_x { period_x } else { 0. },
+                if *flip_y { period_y } else { 0. },
+                if *flip_x { -1 } else { 1 },
+                if *flip_y { -1 } else { 1 },
+                image_element(img, href, tile_width, tile_height)
+            );
+            tiles
+        });
+
+    let id = escape(&format!("pattern-{}", img.id));
+    format!(
+        r#"<pattern id="{}" patternUnits="userSpaceOnUse" width="{}" height="{}" patternTransform="translate({} {}) rotate({})">{}</pattern><rect x="{}" y="{}" width="{}" height="{}" fill="url(#{})"/>"#,
+        id,
+        pattern_width,
+        pattern_height,
+        img.x + img.pattern_x,
+        img.y + img.pattern_y,
+        img.pattern_rotation,
+        tiles,
+        img.x,
+        img.y,
+        img.width,
+        img.height,
+        id
+    )
+}
+
+/// Serializes the layout to an svg document. Every image becomes an `<image>` element at its position and size, or a
+/// rectangle filled with a `<pattern>` if the image is drawn as a pattern; the elements are stacked in the order of
+/// their z-index. The `hrefs` hold the source of every image by image id, usually a data uri; images without source,
+/// like broken images, are left out. The view box is the area that is covered by the exported images, so the
+/// coordinates in the document are the coordinates in the app.
+///
+/// If there is an artboard, the view box is the artboard instead, which clips the images that stick out; images outside
+/// the artboard are left out and a coloured background becomes a rectangle below the images.
+///
+/// Returns `None` if there is nothing to export.
+pub fn to_svg(
+    images: &[ImageData],
+    hrefs: &HashMap<String, String>,
+    artboard: &Artboard,
+) -> Option<String> {
+    let mut exported: Vec<(&ImageData, &String)> = images
+        .iter()
+        .filter(|img| img.width > 0 && img.height > 0)
+        .filter(|img| artboard.shows(&img.bounding_box()))
+        .filter_map(|img| Some((img, hrefs.get(&img.id)?)))
+        .collect();
+    // images with the same z-index are stacked in the order they were added, like in the app
+    exported.sort_by_key(|(img, _)| img.z_index);
+
+    let view_box = exported
+        .iter()
+        .map(|(img, _)| img.bounding_box())
+        .reduce(|a: BoundingBox, b| a.union(&b))?;
+    let view_box = artboard.bounding_box().unwrap_or(view_box);
+
+    let background = artboard.background_rgb().map(|(r, g, b)| {
+        format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"/>"#,
+            view_box.x, view_box.y, view_box.width, view_box.height, r, g, b
+        )
+    });
+    let elements = background
+        .into_iter()
+        .chain(exported.iter().map(|(img, href)| {
+            if img.use_pattern && img.pattern_width > 0 && img.pattern_height > 0 {
+                pattern_elements(img, href)
+            } else {
+                format!(
+                    r#"<g transform="translate({} {})">{}</g>"#,
+                    img.x,
+                    img.y,
+                    image_element(img, href, img.width as f64, img.height as f64)
+                )
+            }
+        }))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    Some(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="{}" height="{}" viewBox="{} {} {} {}">
+{}
+</svg>
+"#,
+        view_box.width,
+        view_box.height,
+        view_box.x,
+        view_box.y,
+        view_box.width,
+        view_box.height,
+        elements
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(id: &str, x: i16, y: i16, width: i16, height: i16, z_index: i16) -> ImageData {
+        ImageData {
+            id: id.to_string(),
+            x,
+            y,
+            width,
+            height,
+            natural_width: width,
+            natural_height: height,
+            z_index,
+            ..Default::default()
+        }
+    }
+
+    fn hrefs(images: &[ImageData]) -> HashMap<String, String> {
+        images
+            .iter()
+            .map(|img| (img.id.clone(), format!("data:image/png;base64,{}", img.id)))
+            .collect()
+    }
+
+    #[test]
+    fn the_view_box_covers_all_images() {
+        let images = vec![
+            image("0", 10, 20, 100, 50, 1),
+            image("1", 200, 100, 50, 50, 2),
+        ];
+        let svg = to_svg(&images, &hrefs(&images), &Artboard::default()).unwrap();
+        assert!(svg.contains(r#"width="240" height="130" viewBox="10 20 240 130""#));
+        assert!(svg.contains(
+            r#"<g transform="translate(10 20)"><image xlink:href="data:image/png;base64,0" width="100" height="50" preserveAspectRatio="none"/></g>"#
+        ));
+    }
+
+    #[test]
+    fn images_are_stacked_by_z_index() {
+        let images = vec![
+            image("top", 0, 0, 10, 10, 3),
+            image("bottom", 0, 0, 10, 10, 1),
+            image("middle", 0, 0, 10, 10, 2),
+        ];
+        let svg = to_svg(&images, &hrefs(&images), &Artboard::default()).unwrap();
+        let position = |id: &str| svg.find(&format!("base64,{}\"", id)).unwrap();
+        assert!(position("bottom") < position("middle"));
+        assert!(position("middle") < position("top"));
+    }
+
+    #[test]
+    fn nothing_is_exported_without_visible_images() {
+        let images = vec![image("0", 0, 0, 0, 10, 1), image("1", 0, 0, 10, 10, 1)];
+        // the second image has no source, like a broken image
+        let mut sources = hrefs(&images);
+        sources.remove("1");
+        assert!(to_svg(&images, &sources, &Artboard::default()).is_none());
+        assert!(to_svg(&[], &HashMap::new(), &Artboard::default()).is_none());
+    }
+
+    #[test]
+    fn patterns_become_pattern_fills() {
+        let mut img = image("0", 10, 10, 200, 100, 1);
+        img.use_pattern = true;
+        img.pattern_width = 40;
+        img.pattern_height = 30;
+        img.pattern_x = 5;
+        img.pattern_y = 6;
+        img.pattern_rotation = 15;
+        img.pattern_repeat = PatternRepeat::Repeat;
+        let images = vec![img.clone()];
+        let svg = to_svg(&images, &hrefs(&images), &Artboard::default()).unwrap();
+        assert!(svg.contains(
+            r#"<pattern id="pattern-0" patternUnits="userSpaceOnUse" width="40" height="30" patternTransform="translate(15 16) rotate(15)">"#
+        ));
+        assert!(svg
+            .contains(r#"<rect x="10" y="10" width="200" height="100" fill="url(#pattern-0)"/>"#));
+
+        // a mirrored tile repeats 4 images, a tile that doesn't repeat is followed by empty space
+        img.pattern_mirror = true;
+        img.pattern_repeat = PatternRepeat::RepeatX;
+        let images = vec![img];
+        let svg = to_svg(&images, &hrefs(&images), &Artboard::default()).unwrap();
+        assert!(svg.contains(r#"width="80" height="371""#));
+        assert_eq!(svg.matches("<image ").count(), 4);
+        assert!(svg.contains(r#"translate(80 60) scale(-1 -1)"#));
+    }
+
+    #[test]
+    fn attributes_are_escaped() {
+        let images = vec![image("a\"b", 0, 0, 10, 10, 1)];
+        let mut sources = HashMap::new();
+        sources.insert(
+            "a\"b".to_string(),
+            "https://example.com/a.png?x=1&y=<2>\"".to_string(),
+        );
+        let mut patterned = images[0].clone();
+        patterned.use_pattern = true;
+        patterned.pattern_width = 5;
+        patterned.pattern_height = 5;
+
+        let svg = to_svg(&images, &sources, &Artboard::default()).unwrap();
+        assert!(svg.contains(r#"xlink:href="https://example.com/a.png?x=1&amp;y=&lt;2&gt;&quot;""#));
+        let svg = to_svg(&[patterned], &sources, &Artboard::default()).unwrap();
+        assert!(svg.contains(r#"<pattern id="pattern-a&quot;b""#));
+        assert!(svg.contains(r#"fill="url(#pattern-a&quot;b)""#));
+    }
+
+    #[test]
+    fn the_artboard_clips_the_export() {
+        let mut artboard = Artboard::default();
+        artboard.set_size(500, 400);
+        let (x, y) = crate::artboard::ORIGIN;
+        let images = vec![
+            image("inside", x + 100, y + 100, 50, 50, 1),
+            image("overlapping", x + 450, y + 350, 100, 100, 2),
+            image("outside", x + 600, y, 50, 50, 3),
+        ];
+        let svg = to_svg(&images, &hrefs(&images), &artboard).unwrap();
+        assert!(svg.contains(&format!(
+            r#"width="500" height="400" viewBox="{} {} 500 400""#,
+            x, y
+        )));
+        assert!(svg.contains(&format!(
+            r#"<rect x="{}" y="{}" width="500" height="400" fill="rgb(255,255,255)"/>"#,
+            x, y
+        )));
+        assert!(svg.contains("base64,inside"));
+        assert!(svg.contains("base64,overlapping"));
+        assert!(!svg.contains("base64,outside"));
+
+        // a transparent artboard has no background
+        artboard.background = None;
+        let svg = to_svg(&images, &hrefs(&images), &artboard).unwrap();
+        assert!(!svg.contains("<rect"));
+    }
+}
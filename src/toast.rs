@@ -0,0 +1,6 @@
+/// Struct that stores a message that is shown to the user until the user dismisses it.
+#[derive(Default, Clone, PartialEq)]
+pub struct Toast {
+    pub id: usize,
+    pub message: String,
+}